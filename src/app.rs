@@ -7,52 +7,86 @@ use crossterm::{
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
-use ratatui::{backend::CrosstermBackend, Terminal};
+use ratatui::{
+    backend::{Backend, CrosstermBackend},
+    Terminal,
+};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashSet, VecDeque};
 use std::io::{self, Stdout};
-use std::time::Duration;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 
 use crate::{
     bash::BashExecutor,
-    claude::{ClaudeProcess, StreamEvent},
+    budget::{parse_cost, parse_token_count, parse_warn_percent, Budget, Budgets, Spend},
+    claude::{
+        context_window, find_claude_command, latest_transcript, list_transcripts,
+        load_claude_commands, load_project_prompt, load_transcript, mcp_server_tools, preflight,
+        project_transcripts_dir, spawn_replay, validate_model, ClaudeCommand, ClaudeOptions,
+        CommandSource, ClaudeProcess, PermissionMode, PreflightReport, SessionInit,
+        StreamEvent, StreamRecord, StreamRecorder, TranscriptItem, TranscriptSummary, MODELS,
+        PROJECT_PROMPT_FILE,
+    },
+    permissions::{
+        PermissionBroker, PermissionDecision, PermissionRequest, PermissionRules,
+        PERMISSION_SERVER_NAME,
+    },
+    config::Config,
+    export::{export_conversation, ExportFormat},
     sessions::SessionManager,
-    ui::{self, InputMode, RenderState},
+    store::{SavedState, Snapshot, StateStore},
+    ui::{self, format_cost, format_tokens, Completion, InputMode, RenderState},
     voice::VoiceRecorder,
 };
 
+/// Identifies the Claude process, bash command or voice job that an
+/// asynchronous result belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RequestId(pub u64);
+
 /// Messages that can be sent to the app from various sources
 #[derive(Debug)]
 pub enum AppMessage {
     /// Claude sent a streaming event
-    ClaudeEvent(StreamEvent),
+    ClaudeEvent(RequestId, StreamEvent),
     /// Claude process finished
-    ClaudeFinished,
+    ClaudeFinished(RequestId),
     /// Claude process error
-    ClaudeError(String),
+    ClaudeError(RequestId, String),
+    /// A line a Claude process wrote to stderr
+    ClaudeStderr(String),
+    /// Startup check of the Claude CLI finished
+    Preflight(PreflightReport),
     /// Bash command output
-    BashOutput(String),
+    BashOutput(RequestId, String),
     /// Bash command finished
-    BashFinished(i32),
+    BashFinished(RequestId, i32),
     /// Voice transcription result
-    VoiceTranscription(String),
+    VoiceTranscription(RequestId, String),
     /// Voice recording error
-    VoiceError(String),
+    VoiceError(RequestId, String),
     /// Session message received
     SessionMessage { from: String, message: String },
+    /// Claude wants to run a tool and needs approval
+    PermissionRequest(PermissionRequest),
+    /// A user message from a replayed recording
+    ReplayInput(RequestId, String),
+    /// Past sessions found in a transcripts directory, for the resume picker
+    TranscriptsListed(PathBuf, Vec<TranscriptSummary>),
 }
 
 /// Application state
 pub struct App {
-    /// Terminal handle
-    terminal: Terminal<CrosstermBackend<Stdout>>,
-    /// Current model
-    model: String,
-    /// Continue previous session
-    continue_session: bool,
-    /// Resume specific session
-    resume_session: Option<String>,
+    /// Options used when spawning the Claude CLI
+    claude_options: ClaudeOptions,
+    /// Directory bash commands and Claude run in, changed by /cd
+    working_dir: PathBuf,
     /// Session ID for this instance
     session_id: Option<String>,
+    /// Claude CLI session reported by the init event
+    claude_session: Option<SessionInit>,
     /// Conversation history for display
     messages: Vec<ConversationEntry>,
     /// Current input text
@@ -62,13 +96,41 @@ pub struct App {
     /// Input mode (normal, recording)
     input_mode: InputMode,
     /// Message queue (for sending while Claude is busy)
-    message_queue: Vec<String>,
+    message_queue: VecDeque<String>,
     /// Is Claude currently processing?
     claude_busy: bool,
     /// Current streaming text buffer
     streaming_buffer: String,
-    /// Claude process handle
+    /// Claude process handle (lives across turns)
     claude_process: Option<ClaudeProcess>,
+    /// Respawn the Claude process before the next message (settings changed)
+    restart_claude: bool,
+    /// The last turn was interrupted; Claude is told with the next message
+    turn_interrupted: bool,
+    /// Records raw Claude output for `--record-stream`
+    stream_recorder: Option<StreamRecorder>,
+    /// Set when showing a recorded stream instead of talking to Claude
+    replay_id: Option<RequestId>,
+    /// Voice job whose transcription we're waiting for
+    voice_job: Option<RequestId>,
+    /// Last id handed out by `next_request_id`
+    last_request_id: u64,
+    /// Claude CLI health, shown by /diagnostics
+    diagnostics: Diagnostics,
+    /// Diagnostics panel is open
+    show_diagnostics: bool,
+    /// File to open in the editor once the event loop has the terminal
+    pending_edit: Option<PathBuf>,
+    /// Lines scrolled up from the end of the stderr history
+    diagnostics_scroll: usize,
+    /// Highlighted row while the model picker is open
+    model_picker: Option<usize>,
+    /// Past sessions listed by /resume
+    resume_sessions: Vec<TranscriptSummary>,
+    /// Highlighted row while the resume picker is open
+    resume_picker: Option<usize>,
+    /// Model the CLI reported it is using; cleared when /model changes it
+    reported_model: Option<String>,
     /// Bash executor
     bash_executor: BashExecutor,
     /// Voice recorder
@@ -81,6 +143,8 @@ pub struct App {
     message_tx: mpsc::Sender<AppMessage>,
     /// Scroll offset for conversation view
     scroll_offset: usize,
+    /// Show subagent transcripts under their Task calls
+    expand_subagents: bool,
     /// Input history
     input_history: Vec<String>,
     /// Current position in input history
@@ -91,17 +155,97 @@ pub struct App {
     status_message: Option<String>,
     /// Token usage tracking
     token_usage: TokenUsage,
+    /// Cost and timing from result events
+    session_cost: SessionCost,
+    /// Tool calls waiting for approval (front one is shown)
+    pending_permissions: VecDeque<PermissionRequest>,
+    /// When the front permission prompt appeared
+    permission_shown_at: Instant,
+    /// `a` was pressed once; pressing it again saves the rule
+    confirm_always_allow: bool,
+    /// Project "always allow" rules
+    permission_rules: PermissionRules,
+    /// Token and cost limits for the session and project
+    budgets: Budgets,
+    /// Limits already warned about, e.g. "session cost"
+    budget_warned: HashSet<String>,
+    /// Set when a budget limit was hit; messages are held until it's raised
+    budget_exceeded: Option<String>,
+    /// Fraction of the context window at which to offer /compact
+    compact_at: f64,
+    /// /compact was offered since the context last passed `compact_at`
+    compact_offered: bool,
+    /// The /compact offer is on screen
+    show_compact_offer: bool,
+    /// When the /compact offer appeared
+    compact_offer_shown_at: Instant,
+    /// CLI and custom commands forwarded to Claude
+    claude_commands: Vec<ClaudeCommand>,
+    /// Where the conversation and draft are saved for crash recovery
+    state_store: Option<StateStore>,
+    /// When the state was last saved
+    state_saved_at: Instant,
+    /// State left by a session that crashed, offered for restoring
+    restore_offer: Option<SavedState>,
+    /// Highlighted code, kept between redraws
+    highlighter: ui::Highlighter,
 }
 
+/// How often the draft and finished entries are saved
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Keys pressed this soon after a prompt pops up are ignored; they were
+/// typed for whatever was on screen before
+const PROMPT_GRACE: Duration = Duration::from_millis(500);
+
+/// Commands handled here, as shown in /help: usage and description
+const APP_COMMANDS: &[(&str, &str)] = &[
+    ("quit", "Exit"),
+    ("clear", "Clear conversation"),
+    ("model [name]", "Pick or set model"),
+    ("resume [id]", "Pick a past session to continue"),
+    ("session", "Show Claude session details"),
+    ("cost", "Show cost, duration and turns"),
+    ("permissions", "Show or change permission mode and tool lists"),
+    ("system [edit]", "Show the system prompt or edit the project prompt"),
+    ("mcp", "Show MCP servers, their status and tools"),
+    ("export [fmt]", "Save the conversation as md, html or json"),
+    ("budget", "Show or set token and cost budgets"),
+    ("cd <path>", "Change directory for bash and Claude"),
+    ("add-dir <p>", "Give Claude access to another directory"),
+    ("diagnostics", "Show Claude CLI version, checks and stderr"),
+    ("sessions", "List active sessions"),
+    ("send <id> <m>", "Send message to session"),
+    ("broadcast <m>", "Broadcast to all sessions"),
+    ("inbox", "Read incoming messages"),
+    ("help", "Show this help"),
+];
+
+/// Default for `compact_at`
+pub const DEFAULT_COMPACT_AT: f64 = 0.8;
+
 /// A single entry in the conversation
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ConversationEntry {
     pub role: Role,
     pub content: ConversationContent,
     pub timestamp: chrono::DateTime<chrono::Utc>,
+    /// Entries produced by a subagent (Task tool call) under this entry
+    pub children: Vec<ConversationEntry>,
+}
+
+impl ConversationEntry {
+    pub fn new(role: Role, content: ConversationContent) -> Self {
+        Self {
+            role,
+            content,
+            timestamp: chrono::Utc::now(),
+            children: Vec::new(),
+        }
+    }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum Role {
     User,
     Assistant,
@@ -110,13 +254,38 @@ pub enum Role {
     Bash,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum ConversationContent {
     Text(String),
-    ToolUse { name: String, input: String },
+    /// A tool call, tracked by id until its result arrives
+    ToolUse {
+        id: String,
+        name: String,
+        input: String,
+        status: ToolStatus,
+        result: Option<String>,
+        finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    },
+    /// A tool result with no matching tool call
     ToolResult { name: String, result: String },
     Thinking(String),
-    BashCommand { command: String, output: String, exit_code: i32 },
+    /// A `!` command; `exit_code` is unset while it runs
+    BashCommand {
+        id: RequestId,
+        command: String,
+        output: String,
+        exit_code: Option<i32>,
+    },
+}
+
+/// Progress of a tool call
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ToolStatus {
+    Pending,
+    Success,
+    Error,
+    /// Still running when the user interrupted the turn
+    Interrupted,
 }
 
 #[derive(Debug, Default, Clone)]
@@ -125,61 +294,230 @@ pub struct TokenUsage {
     pub output_tokens: u64,
     pub cache_read_tokens: u64,
     pub cache_write_tokens: u64,
+    /// Prompt size of the latest main-conversation request: input plus
+    /// cache reads and writes
+    pub context_tokens: u64,
 }
 
-impl App {
-    pub fn new(model: String, continue_session: bool, resume_session: Option<String>) -> Result<Self> {
-        // Set up terminal
-        enable_raw_mode()?;
-        let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen, SetCursorStyle::SteadyBar)?;
-        let backend = CrosstermBackend::new(stdout);
-        let terminal = Terminal::new(backend)?;
+/// Cost and timing of a single request, from its result event
+#[derive(Debug, Default, Clone)]
+pub struct TurnCost {
+    pub cost_usd: f64,
+    pub duration_ms: u64,
+    pub duration_api_ms: u64,
+    pub num_turns: u32,
+    pub is_error: bool,
+}
+
+/// Cost and timing accumulated over the session
+#[derive(Debug, Default, Clone)]
+pub struct SessionCost {
+    pub requests: u32,
+    pub cost_usd: f64,
+    pub duration_ms: u64,
+    pub duration_api_ms: u64,
+    pub num_turns: u32,
+    pub last: Option<TurnCost>,
+}
+
+impl TokenUsage {
+    /// Every token processed, including cache reads and writes
+    pub fn total(&self) -> u64 {
+        self.input_tokens + self.output_tokens + self.cache_read_tokens + self.cache_write_tokens
+    }
+}
+
+impl SessionCost {
+    pub fn add(&mut self, turn: TurnCost) {
+        self.requests += 1;
+        self.cost_usd += turn.cost_usd;
+        self.duration_ms += turn.duration_ms;
+        self.duration_api_ms += turn.duration_api_ms;
+        self.num_turns += turn.num_turns;
+        self.last = Some(turn);
+    }
+}
+
+/// Prefixed to the first message after an interrupt
+const INTERRUPTED_NOTE: &str = "[The user interrupted your previous response before it finished.]";
+
+/// Stderr lines kept for the diagnostics panel
+const STDERR_HISTORY_LINES: usize = 1000;
 
+/// Claude CLI health shown by /diagnostics
+#[derive(Debug, Default, Clone)]
+pub struct Diagnostics {
+    /// Executable used to start Claude
+    pub claude_bin: String,
+    /// Startup check results, once it has run
+    pub preflight: Option<PreflightReport>,
+    /// Stderr from every Claude process this session (oldest dropped first)
+    pub stderr: VecDeque<String>,
+    /// Most recent process failure
+    pub last_error: Option<String>,
+}
+
+impl App {
+    pub fn new(mut claude_options: ClaudeOptions) -> Result<Self> {
         // Create message channel
         let (message_tx, message_rx) = mpsc::channel(100);
 
         // Initialize components
-        let bash_executor = BashExecutor::new(message_tx.clone());
+        let working_dir = match claude_options.working_dir.clone() {
+            Some(dir) => dir,
+            None => std::env::current_dir()?,
+        };
+        claude_options.working_dir = Some(working_dir.clone());
+        let bash_executor = BashExecutor::new(message_tx.clone(), working_dir.clone());
         let voice_recorder = VoiceRecorder::new(message_tx.clone());
         let session_manager = SessionManager::new(message_tx.clone())?;
 
-        Ok(Self {
-            terminal,
-            model,
-            continue_session,
-            resume_session,
+        // Route tool approvals through the TUI
+        let permission_broker = PermissionBroker::start(message_tx.clone())?;
+        claude_options.permission_bridge = Some(permission_broker.mcp_config()?);
+        let permission_rules = PermissionRules::load(&working_dir);
+        let budgets = Budgets {
+            project_base: Spend::load(&working_dir),
+            ..Default::default()
+        };
+        claude_options.project_prompt = load_project_prompt(&working_dir);
+        let claude_commands = load_claude_commands(&working_dir);
+        let diagnostics = Diagnostics {
+            claude_bin: claude_options.program().display().to_string(),
+            ..Default::default()
+        };
+
+        let mut app = Self {
+            claude_options,
+            working_dir,
             session_id: None,
+            claude_session: None,
             messages: Vec::new(),
             input: String::new(),
             cursor_position: 0,
             input_mode: InputMode::Normal,
-            message_queue: Vec::new(),
+            message_queue: VecDeque::new(),
             claude_busy: false,
             streaming_buffer: String::new(),
             claude_process: None,
+            restart_claude: false,
+            turn_interrupted: false,
+            stream_recorder: None,
+            replay_id: None,
+            voice_job: None,
+            last_request_id: 0,
+            diagnostics,
+            show_diagnostics: false,
+            pending_edit: None,
+            diagnostics_scroll: 0,
+            model_picker: None,
+            resume_sessions: Vec::new(),
+            resume_picker: None,
+            reported_model: None,
             bash_executor,
             voice_recorder,
             session_manager,
             message_rx,
             message_tx,
             scroll_offset: 0,
+            expand_subagents: false,
             input_history: Vec::new(),
             history_index: None,
             should_quit: false,
             status_message: None,
             token_usage: TokenUsage::default(),
-        })
+            session_cost: SessionCost::default(),
+            pending_permissions: VecDeque::new(),
+            permission_shown_at: Instant::now(),
+            confirm_always_allow: false,
+            permission_rules,
+            budgets,
+            budget_warned: HashSet::new(),
+            budget_exceeded: None,
+            compact_at: DEFAULT_COMPACT_AT,
+            compact_offered: false,
+            show_compact_offer: false,
+            compact_offer_shown_at: Instant::now(),
+            claude_commands,
+            state_store: None,
+            state_saved_at: Instant::now(),
+            restore_offer: None,
+            highlighter: ui::Highlighter::default(),
+        };
+        app.show_resumed_history();
+        app.warn_unknown_models();
+        Ok(app)
+    }
+
+    /// Set the session and project budgets
+    pub fn set_budgets(&mut self, session: Budget, project: Budget) {
+        self.budgets.session = session;
+        self.budgets.project = project;
+        self.claude_options.max_turns = self.budgets.max_turns();
+    }
+
+    /// Offer /compact once this fraction of the context window is used;
+    /// 1 or more never offers
+    pub fn set_compact_at(&mut self, fraction: f64) {
+        self.compact_at = fraction;
+    }
+
+    /// Number the lines of code blocks and files
+    pub fn set_line_numbers(&mut self, line_numbers: bool) {
+        self.highlighter.line_numbers = line_numbers;
+    }
+
+    /// Save the conversation and draft to `store` as they change
+    pub fn set_state_store(&mut self, store: StateStore) {
+        self.state_store = Some(store);
+        self.save_state();
+    }
+
+    /// Offer to restore what a crashed session left behind
+    pub fn offer_restore(&mut self, saved: SavedState) {
+        self.restore_offer = Some(saved);
+    }
+
+    /// Record every Claude process started from now on
+    pub fn record_stream(&mut self, recorder: StreamRecorder) {
+        self.stream_recorder = Some(recorder);
+    }
+
+    /// Play back a recorded stream instead of spawning Claude
+    pub fn replay(&mut self, records: Vec<StreamRecord>, source: &str) {
+        let id = self.next_request_id();
+        self.replay_id = Some(id);
+        self.messages.push(ConversationEntry::new(
+            Role::System,
+            ConversationContent::Text(format!("Replaying {}", source)),
+        ));
+        spawn_replay(id, records, self.message_tx.clone());
+    }
+
+    fn next_request_id(&mut self) -> RequestId {
+        self.last_request_id += 1;
+        RequestId(self.last_request_id)
+    }
+
+    /// Whether a Claude message comes from the live process (or replay).
+    /// Anything else is left over from a process we've since dropped.
+    fn is_current_claude(&self, id: RequestId) -> bool {
+        self.claude_process.as_ref().is_some_and(|p| p.id() == id) || self.replay_id == Some(id)
     }
 
     /// Main event loop
-    pub async fn run(&mut self) -> Result<()> {
+    pub async fn run<B: Backend>(&mut self, terminal: &mut Terminal<B>) -> Result<()> {
         // Register with session manager
-        self.session_id = Some(self.session_manager.register("interactive").await?);
+        let session_id = self.session_manager.register("interactive", &self.working_dir).await?;
+        self.session_id = Some(session_id);
+
+        if self.replay_id.is_none() {
+            self.start_preflight();
+        }
 
         loop {
             // Draw UI
-            self.draw()?;
+            self.draw(terminal)?;
 
             // Handle events with timeout
             tokio::select! {
@@ -193,11 +531,19 @@ impl App {
                 }
 
                 // Check for app messages
-                Some(msg) = self.message_rx.recv() => {
+                Some(msg) = self.next_message() => {
                     self.handle_app_message(msg).await?;
                 }
             }
 
+            if self.state_saved_at.elapsed() >= STATE_SAVE_INTERVAL {
+                self.save_state();
+            }
+
+            if let Some(path) = self.pending_edit.take() {
+                self.edit_file(terminal, &path).await?;
+            }
+
             if self.should_quit {
                 break;
             }
@@ -208,7 +554,340 @@ impl App {
         Ok(())
     }
 
-    fn draw(&mut self) -> Result<()> {
+    /// Open `path` in the user's editor, handing it the terminal meanwhile
+    async fn edit_file<B: Backend>(
+        &mut self,
+        terminal: &mut Terminal<B>,
+        path: &Path,
+    ) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        if !path.exists() {
+            std::fs::write(path, "")?;
+        }
+
+        disable_raw_mode()?;
+        execute!(io::stdout(), LeaveAlternateScreen)?;
+        let status = run_editor(path).await;
+        enable_raw_mode()?;
+        execute!(io::stdout(), EnterAlternateScreen)?;
+        terminal.clear()?;
+
+        match status {
+            Ok(()) => self.reload_project_prompt(),
+            Err(e) => self.status_message = Some(format!("Editor failed: {}", e)),
+        }
+        Ok(())
+    }
+
+    /// Move bash and Claude to `dir`, picking up that project's prompt and
+    /// permission rules. The CLI keeps sessions per project directory, so
+    /// Claude starts a new session there.
+    async fn change_dir(&mut self, dir: PathBuf) {
+        if dir == self.working_dir {
+            self.status_message = Some(format!("Already in {}", dir.display()));
+            return;
+        }
+        if self.claude_busy {
+            self.status_message =
+                Some("Wait for Claude to finish before changing directory".to_string());
+            return;
+        }
+        self.bash_executor.set_working_dir(&dir);
+        self.claude_options.working_dir = Some(dir.clone());
+        self.claude_options.project_prompt = load_project_prompt(&dir);
+        self.permission_rules = PermissionRules::load(&dir);
+        self.claude_commands = load_claude_commands(&dir);
+        self.restart_claude = true;
+        self.claude_session = None;
+        self.claude_options.resume_session = None;
+        self.claude_options.continue_session = false;
+        self.turn_interrupted = false;
+        self.token_usage.context_tokens = 0;
+
+        // Budgets and spending are tracked per project
+        self.save_project_spend();
+        self.budgets.project = match Config::load(&dir) {
+            Ok(config) => config.project_budget,
+            Err(e) => {
+                tracing::debug!("Failed to load config for {}: {}", dir.display(), e);
+                Budget::default()
+            }
+        };
+        self.budgets.project_base = Spend::load(&dir);
+        self.budgets.project_start = self.session_spend();
+        self.claude_options.max_turns = self.budgets.max_turns();
+        if let Err(e) = self.session_manager.update_cwd(&dir).await {
+            tracing::debug!("Failed to update session cwd: {}", e);
+        }
+        self.messages.push(ConversationEntry::new(
+            Role::System,
+            ConversationContent::Text(format!(
+                "Working directory: {}. Claude starts a new session here with the next \
+                 message and won't remember the conversation above.",
+                dir.display()
+            )),
+        ));
+        self.working_dir = dir;
+        self.check_budgets();
+    }
+
+    /// Context window of the model in use
+    fn context_window(&self) -> u64 {
+        context_window(self.reported_model.as_deref().unwrap_or(&self.claude_options.model))
+    }
+
+    /// Offer /compact once the context passes `compact_at`. The offer is
+    /// made again only after the context drops back below it.
+    fn check_context(&mut self) {
+        let used = self.token_usage.context_tokens as f64 / self.context_window() as f64;
+        if used < self.compact_at {
+            self.compact_offered = false;
+        } else if !self.compact_offered && self.replay_id.is_none() {
+            self.compact_offered = true;
+            self.show_compact_offer = true;
+            self.compact_offer_shown_at = Instant::now();
+        }
+    }
+
+    /// What this session has spent so far
+    fn session_spend(&self) -> Spend {
+        Spend {
+            tokens: self.token_usage.total(),
+            cost_usd: self.session_cost.cost_usd,
+        }
+    }
+
+    /// Warn near a budget limit. Past one, stop the running turn and hold
+    /// new messages until the budget is raised.
+    fn check_budgets(&mut self) {
+        if self.replay_id.is_some() {
+            return;
+        }
+        let alerts = self.budgets.check(self.session_spend());
+        // Raising a budget re-arms its warning
+        self.budget_warned
+            .retain(|limit| alerts.iter().any(|alert| alert.limit == *limit));
+
+        for alert in alerts.iter().filter(|alert| !alert.exceeded) {
+            if self.budget_warned.insert(alert.limit.clone()) {
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(format!("Warning: {}", alert.message)),
+                ));
+            }
+        }
+
+        let exceeded = alerts.into_iter().find(|alert| alert.exceeded);
+        match exceeded {
+            Some(alert) if self.budget_exceeded.is_none() => {
+                if self.claude_busy {
+                    self.interrupt_claude();
+                }
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(format!(
+                        "Stopped: {}. New messages are held until you raise the budget \
+                         with /budget.",
+                        alert.message
+                    )),
+                ));
+                self.budget_exceeded = Some(alert.message);
+            }
+            Some(_) => {}
+            None => self.budget_exceeded = None,
+        }
+    }
+
+    /// Record the project's spending while it has a budget
+    fn save_project_spend(&self) {
+        if self.replay_id.is_some() || self.budgets.project == Budget::default() {
+            return;
+        }
+        let spend = self.budgets.project_spend(self.session_spend());
+        if let Err(e) = spend.save(&self.working_dir) {
+            tracing::debug!("Failed to save project spending: {}", e);
+        }
+    }
+
+    /// `/budget [session|project] <tokens|cost|turns|warn> <value|off>`;
+    /// with no arguments, show the budgets and spending
+    async fn handle_budget_command(&mut self, args: &str) -> Result<()> {
+        let mut words: Vec<&str> = args.split_whitespace().collect();
+        if words.is_empty() {
+            let text = self.budget_summary();
+            self.messages.push(ConversationEntry::new(
+                Role::System,
+                ConversationContent::Text(text),
+            ));
+            return Ok(());
+        }
+
+        let scope = match words[0] {
+            "session" | "project" => words.remove(0),
+            _ => "session",
+        };
+        let budget = if scope == "project" {
+            &mut self.budgets.project
+        } else {
+            &mut self.budgets.session
+        };
+        let updated = match words.as_slice() {
+            ["tokens", value] => set_limit(&mut budget.max_tokens, value, parse_token_count),
+            ["cost", value] => set_limit(&mut budget.max_cost_usd, value, parse_cost),
+            ["turns", value] => set_limit(&mut budget.max_turns, value, |v| v.parse().ok()),
+            ["warn", value] => set_limit(&mut budget.warn_at, value, parse_warn_percent),
+            _ => false,
+        };
+        if !updated {
+            self.status_message = Some(
+                "Usage: /budget [session|project] <tokens|cost|turns|warn> <value|off>".to_string(),
+            );
+            return Ok(());
+        }
+
+        let max_turns = self.budgets.max_turns();
+        if max_turns != self.claude_options.max_turns {
+            self.claude_options.max_turns = max_turns;
+            self.restart_claude = true;
+        }
+        let budget = if scope == "project" {
+            &self.budgets.project
+        } else {
+            &self.budgets.session
+        };
+        self.status_message = Some(format!("{} budget: {}", scope, budget.describe()));
+
+        // A raised budget releases held messages
+        let was_exceeded = self.budget_exceeded.is_some();
+        self.check_budgets();
+        if was_exceeded && self.budget_exceeded.is_none() && !self.claude_busy {
+            self.send_queued().await?;
+        }
+        Ok(())
+    }
+
+    /// `/export [md|html|json] [path] [--thinking]`; the format defaults to
+    /// the path's extension, then Markdown
+    fn handle_export_command(&mut self, args: &str) {
+        let mut format = None;
+        let mut path = None;
+        let mut include_thinking = false;
+        let mut force = false;
+        for word in args.split_whitespace() {
+            match word {
+                "--thinking" => include_thinking = true,
+                "--force" => force = true,
+                _ if format.is_none() && path.is_none() && ExportFormat::parse(word).is_some() => {
+                    format = ExportFormat::parse(word);
+                }
+                _ if path.is_none() => path = Some(word),
+                _ => {
+                    self.status_message = Some(
+                        "Usage: /export [md|html|json] [path] [--thinking] [--force]".to_string(),
+                    );
+                    return;
+                }
+            }
+        }
+        if self.messages.is_empty() {
+            self.status_message = Some("Nothing to export yet".to_string());
+            return;
+        }
+
+        let format = format
+            .or_else(|| {
+                let extension = Path::new(path?).extension()?.to_str()?;
+                ExportFormat::parse(extension)
+            })
+            .unwrap_or(ExportFormat::Markdown);
+        let path = match path {
+            Some(path) => resolve_path(&self.working_dir, path),
+            None => Ok(self.working_dir.join(format!(
+                "conversation-{}.{}",
+                chrono::Local::now().format("%Y%m%d-%H%M%S"),
+                format.extension()
+            ))),
+        };
+        let content = export_conversation(&self.messages, format, include_thinking);
+        // Don't clobber a file that's already there unless asked to
+        let result = path.and_then(|path| {
+            let mut options = std::fs::OpenOptions::new();
+            options.write(true);
+            if force {
+                options.create(true).truncate(true);
+            } else {
+                options.create_new(true);
+            }
+            let written = options.open(&path).and_then(|mut file| {
+                io::Write::write_all(&mut file, content.as_bytes())
+            });
+            match written {
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(anyhow::anyhow!(
+                    "add --force to overwrite {}",
+                    path.display()
+                )),
+                Err(e) => Err(anyhow::anyhow!("{}: {}", path.display(), e)),
+                Ok(()) => Ok(path),
+            }
+        });
+        self.status_message = Some(match result {
+            Ok(path) => format!("Exported {} entries to {}", self.messages.len(), path.display()),
+            Err(e) => format!("Export failed: {}", e),
+        });
+    }
+
+    /// Describe the budgets and what has been spent against them
+    fn budget_summary(&self) -> String {
+        let session = self.session_spend();
+        let spent = |spend: Spend| {
+            format!("{} tokens, {}", format_tokens(spend.tokens), format_cost(spend.cost_usd))
+        };
+        let mut text = format!(
+            "Session budget: {}\n  Spent: {}\nProject budget: {}\n  Spent: {}",
+            self.budgets.session.describe(),
+            spent(session),
+            self.budgets.project.describe(),
+            spent(self.budgets.project_spend(session)),
+        );
+        if let Some(max_turns) = self.budgets.max_turns() {
+            text.push_str(&format!("\nMax turns per message: {}", max_turns));
+        }
+        if let Some(reason) = &self.budget_exceeded {
+            text.push_str(&format!("\nStopped: {}", reason));
+        }
+        text
+    }
+
+    /// Pick up changes to the project prompt file
+    fn reload_project_prompt(&mut self) {
+        let prompt = load_project_prompt(&self.working_dir);
+        if prompt == self.claude_options.project_prompt {
+            self.status_message = Some("Project prompt unchanged".to_string());
+            return;
+        }
+        self.claude_options.project_prompt = prompt;
+        self.restart_claude = true;
+        self.status_message = Some("Project prompt updated (applies to next message)".to_string());
+    }
+
+    /// Check the Claude CLI in the background; problems show up as warnings
+    fn start_preflight(&self) {
+        let options = self.claude_options.clone();
+        let tx = self.message_tx.clone();
+        tokio::spawn(async move {
+            let report = preflight(&options).await;
+            let _ = tx.send(AppMessage::Preflight(report)).await;
+        });
+    }
+
+    /// Wait for the next message from Claude, bash, voice or sessions
+    pub(crate) async fn next_message(&mut self) -> Option<AppMessage> {
+        self.message_rx.recv().await
+    }
+
+    pub(crate) fn draw<B: Backend>(&self, terminal: &mut Terminal<B>) -> Result<()> {
         // Extract state for rendering
         let state = RenderState {
             messages: &self.messages,
@@ -217,20 +896,64 @@ impl App {
             input_mode: self.input_mode,
             claude_busy: self.claude_busy,
             streaming_buffer: &self.streaming_buffer,
-            model: &self.model,
+            model: self.reported_model.as_deref().unwrap_or(&self.claude_options.model),
+            working_dir: &self.working_dir,
+            budget_exceeded: self.budget_exceeded.is_some(),
+            permission_mode: self.claude_options.permission_mode,
             scroll_offset: self.scroll_offset,
+            expand_subagents: self.expand_subagents,
             status_message: self.status_message.as_deref(),
             token_usage: &self.token_usage,
+            session_cost: &self.session_cost,
             message_queue_len: self.message_queue.len(),
+            permission_request: self.pending_permissions.front(),
+            pending_permissions: self.pending_permissions.len(),
+            confirm_always_allow: self.confirm_always_allow,
+            diagnostics: self.show_diagnostics.then_some(&self.diagnostics),
+            diagnostics_scroll: self.diagnostics_scroll,
+            model_picker: self.model_picker,
+            resume_picker: self
+                .resume_picker
+                .map(|selected| (self.resume_sessions.as_slice(), selected)),
+            context_window: self.context_window(),
+            compact_at: self.compact_at,
+            compact_offer: self.show_compact_offer,
+            restore_offer: self.restore_offer.as_ref(),
+            completions: self.completions(),
+            highlighter: &self.highlighter,
         };
 
-        self.terminal.draw(|frame| {
+        terminal.draw(|frame| {
             ui::draw(frame, &state);
         })?;
+        self.highlighter.sweep();
         Ok(())
     }
 
-    async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+    pub(crate) async fn handle_key_event(&mut self, key: KeyEvent) -> Result<()> {
+        // A pending permission prompt takes all input until answered
+        if !self.pending_permissions.is_empty() {
+            return self.handle_permission_key(key).await;
+        }
+        if self.show_diagnostics {
+            self.handle_diagnostics_key(key);
+            return Ok(());
+        }
+        if let Some(selected) = self.model_picker {
+            self.handle_model_picker_key(key, selected);
+            return Ok(());
+        }
+        if let Some(selected) = self.resume_picker {
+            self.handle_resume_picker_key(key, selected);
+            return Ok(());
+        }
+        if self.show_compact_offer {
+            return self.handle_compact_offer_key(key).await;
+        }
+        if self.restore_offer.is_some() {
+            return self.handle_restore_offer_key(key).await;
+        }
+
         match self.input_mode {
             InputMode::Normal => self.handle_normal_mode_key(key).await?,
             InputMode::Recording => self.handle_recording_mode_key(key).await?,
@@ -247,17 +970,17 @@ impl App {
             (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
                 // Interrupt Claude if busy
                 if self.claude_busy {
-                    if let Some(ref mut process) = self.claude_process {
-                        process.abort().await;
-                        self.claude_busy = false;
-                        self.status_message = Some("Interrupted".to_string());
-                    }
+                    self.interrupt_claude();
                 } else {
                     // Clear input if not busy
                     self.input.clear();
                     self.cursor_position = 0;
                 }
             }
+            // Expand or collapse subagent transcripts (Ctrl+O)
+            (KeyModifiers::CONTROL, KeyCode::Char('o')) => {
+                self.expand_subagents = !self.expand_subagents;
+            }
             // Delete word backward (Ctrl+W)
             (KeyModifiers::CONTROL, KeyCode::Char('w')) => {
                 self.delete_word_backward();
@@ -287,6 +1010,14 @@ impl App {
             (KeyModifiers::CONTROL, KeyCode::Char('e')) => {
                 self.cursor_position = self.input.len();
             }
+            // Complete a slash command
+            (_, KeyCode::Tab) => {
+                self.complete_command();
+            }
+            // Cycle permission mode for the next turn (Shift+Tab)
+            (_, KeyCode::BackTab) => {
+                self.set_permission_mode(self.claude_options.permission_mode.next());
+            }
             // Submit input
             (_, KeyCode::Enter) => {
                 if !self.input.is_empty() {
@@ -351,6 +1082,151 @@ impl App {
         Ok(())
     }
 
+    async fn handle_permission_key(&mut self, key: KeyEvent) -> Result<()> {
+        if (key.modifiers, key.code) == (KeyModifiers::CONTROL, KeyCode::Char('q')) {
+            self.should_quit = true;
+            return Ok(());
+        }
+        if self.permission_shown_at.elapsed() < PROMPT_GRACE {
+            return Ok(());
+        }
+        // Saving a rule takes a second `a`; Esc goes back to the prompt
+        let decision = match (key.modifiers, key.code) {
+            (_, KeyCode::Char('y')) => PermissionDecision::Allow,
+            (_, KeyCode::Char('a')) if self.confirm_always_allow => {
+                PermissionDecision::AlwaysAllow
+            }
+            (_, KeyCode::Char('a')) => {
+                self.confirm_always_allow = true;
+                return Ok(());
+            }
+            (_, KeyCode::Esc) if self.confirm_always_allow => {
+                self.confirm_always_allow = false;
+                return Ok(());
+            }
+            (_, KeyCode::Char('n') | KeyCode::Esc) => PermissionDecision::Deny,
+            (KeyModifiers::CONTROL, KeyCode::Char('c')) => PermissionDecision::Deny,
+            _ => return Ok(()),
+        };
+
+        self.confirm_always_allow = false;
+        self.permission_shown_at = Instant::now();
+        if let Some(request) = self.pending_permissions.pop_front() {
+            if decision == PermissionDecision::AlwaysAllow {
+                match self.permission_rules.add(&request.tool_name, &request.input) {
+                    Ok(rule) => self.status_message = Some(format!("Always allowing {}", rule)),
+                    Err(e) => self.status_message = Some(format!("Failed to save rule: {}", e)),
+                }
+            } else if decision == PermissionDecision::Deny {
+                self.status_message = Some(format!("Denied {}", request.tool_name));
+            }
+            let _ = request.responder.send(decision);
+        }
+        Ok(())
+    }
+
+    fn handle_diagnostics_key(&mut self, key: KeyEvent) {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc | KeyCode::Enter | KeyCode::Char('q')) => {
+                self.show_diagnostics = false;
+            }
+            (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+                self.show_diagnostics = false;
+            }
+            (_, KeyCode::Up) => {
+                self.diagnostics_scroll = self.diagnostics_scroll.saturating_add(1);
+            }
+            (_, KeyCode::Down) => {
+                self.diagnostics_scroll = self.diagnostics_scroll.saturating_sub(1);
+            }
+            (_, KeyCode::PageUp) => {
+                self.diagnostics_scroll = self.diagnostics_scroll.saturating_add(10);
+            }
+            (_, KeyCode::PageDown) => {
+                self.diagnostics_scroll = self.diagnostics_scroll.saturating_sub(10);
+            }
+            _ => {}
+        }
+    }
+
+    async fn handle_compact_offer_key(&mut self, key: KeyEvent) -> Result<()> {
+        // The offer pops up unasked, maybe mid-sentence
+        if self.compact_offer_shown_at.elapsed() < PROMPT_GRACE {
+            return Ok(());
+        }
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Char('y')) => {
+                self.show_compact_offer = false;
+                self.send_to_claude("/compact").await?;
+            }
+            (_, KeyCode::Char('n') | KeyCode::Esc)
+            | (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+                self.show_compact_offer = false;
+                self.status_message = Some("Run /compact when you're ready".to_string());
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn handle_restore_offer_key(&mut self, key: KeyEvent) -> Result<()> {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Char('y') | KeyCode::Enter) => {
+                if let Some(saved) = self.restore_offer.take() {
+                    self.restore(saved).await?;
+                }
+            }
+            (_, KeyCode::Char('n') | KeyCode::Esc)
+            | (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+                if let Some(saved) = self.restore_offer.take() {
+                    saved.discard();
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn handle_model_picker_key(&mut self, key: KeyEvent, selected: usize) {
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+                self.model_picker = None;
+            }
+            (_, KeyCode::Up) => {
+                self.model_picker = Some(selected.checked_sub(1).unwrap_or(MODELS.len() - 1));
+            }
+            (_, KeyCode::Down) => {
+                self.model_picker = Some((selected + 1) % MODELS.len());
+            }
+            (_, KeyCode::Enter) => {
+                self.model_picker = None;
+                self.set_model(MODELS[selected].cli_name());
+            }
+            _ => {}
+        }
+    }
+
+    fn handle_resume_picker_key(&mut self, key: KeyEvent, selected: usize) {
+        let count = self.resume_sessions.len();
+        match (key.modifiers, key.code) {
+            (_, KeyCode::Esc) | (KeyModifiers::CONTROL, KeyCode::Char('c')) => {
+                self.resume_picker = None;
+            }
+            (_, KeyCode::Up) => {
+                self.resume_picker = Some(selected.checked_sub(1).unwrap_or(count - 1));
+            }
+            (_, KeyCode::Down) => {
+                self.resume_picker = Some((selected + 1) % count);
+            }
+            (_, KeyCode::Enter) => {
+                self.resume_picker = None;
+                let session = self.resume_sessions.swap_remove(selected);
+                self.resume_session(&session.session_id, &session.path);
+            }
+            _ => {}
+        }
+    }
+
     async fn handle_recording_mode_key(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
             // Stop recording
@@ -360,6 +1236,7 @@ impl App {
             // Cancel recording
             KeyCode::Esc => {
                 self.voice_recorder.cancel().await;
+                self.voice_job = None;
                 self.input_mode = InputMode::Normal;
                 self.status_message = Some("Recording cancelled".to_string());
             }
@@ -395,14 +1272,19 @@ impl App {
     }
 
     async fn execute_bash(&mut self, command: &str) -> Result<()> {
-        // Add to conversation
-        self.messages.push(ConversationEntry {
-            role: Role::Bash,
-            content: ConversationContent::Text(format!("$ {}", command)),
-            timestamp: chrono::Utc::now(),
-        });
+        // Add to conversation; output is filled in when it arrives
+        let id = self.next_request_id();
+        self.messages.push(ConversationEntry::new(
+            Role::Bash,
+            ConversationContent::BashCommand {
+                id,
+                command: command.to_string(),
+                output: String::new(),
+                exit_code: None,
+            },
+        ));
 
-        self.bash_executor.execute(command).await?;
+        self.bash_executor.execute(id, command).await?;
         Ok(())
     }
 
@@ -418,31 +1300,187 @@ impl App {
             "clear" => {
                 self.messages.clear();
                 self.scroll_offset = 0;
+                self.clear_saved_entries();
             }
             "model" => {
-                if !args.is_empty() {
-                    self.model = args.to_string();
-                    self.status_message = Some(format!("Model set to: {}", self.model));
+                if !args.trim().is_empty() {
+                    self.set_model(args.trim());
                 } else {
-                    self.status_message = Some(format!("Current model: {}", self.model));
+                    let current = &self.claude_options.model;
+                    let selected = MODELS.iter().position(|model| model.matches(current));
+                    self.model_picker = Some(selected.unwrap_or(0));
                 }
             }
-            "sessions" => {
-                let sessions = self.session_manager.list_sessions().await?;
-                let msg = if sessions.is_empty() {
-                    "No other active sessions".to_string()
-                } else {
-                    sessions
-                        .iter()
+            "export" => self.handle_export_command(args),
+            "resume" => {
+                let Some(dir) = project_transcripts_dir(&self.working_dir) else {
+                    self.status_message = Some("No home directory to find sessions in".to_string());
+                    return Ok(());
+                };
+                let id = args.trim();
+                if !id.is_empty() {
+                    let path = dir.join(format!("{}.jsonl", id));
+                    if path.exists() {
+                        self.resume_session(id, &path);
+                    } else {
+                        self.status_message =
+                            Some(format!("No session {} in {}", id, dir.display()));
+                    }
+                    return Ok(());
+                }
+                // Summaries read every transcript, which can take a while
+                self.status_message = Some("Looking for past sessions...".to_string());
+                let tx = self.message_tx.clone();
+                tokio::task::spawn_blocking(move || {
+                    let sessions = list_transcripts(&dir);
+                    let _ = tx.blocking_send(AppMessage::TranscriptsListed(dir, sessions));
+                });
+            }
+            "session" => {
+                let text = match &self.claude_session {
+                    Some(session) => {
+                        let mut text = format!(
+                            "Claude session: {}\n  Model: {}\n  Cwd: {}\n  Tools: {}",
+                            session.session_id,
+                            session.model,
+                            session.cwd,
+                            session.tools.join(", ")
+                        );
+                        if !session.mcp_servers.is_empty() {
+                            let servers: Vec<_> = session
+                                .mcp_servers
+                                .iter()
+                                .map(|s| format!("{} ({})", s.name, s.status))
+                                .collect();
+                            text.push_str(&format!("\n  MCP servers: {}", servers.join(", ")));
+                        }
+                        text
+                    }
+                    None => "No Claude session yet (send a message to start one)".to_string(),
+                };
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(text),
+                ));
+            }
+            "mcp" => {
+                let text = self.mcp_summary();
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(text),
+                ));
+            }
+            "system" => match args.trim() {
+                "" => {
+                    let text = self.system_prompt_summary();
+                    self.messages.push(ConversationEntry::new(
+                        Role::System,
+                        ConversationContent::Text(text),
+                    ));
+                }
+                "edit" => {
+                    self.pending_edit = Some(self.working_dir.join(PROJECT_PROMPT_FILE));
+                }
+                _ => {
+                    self.status_message = Some("Usage: /system [edit]".to_string());
+                }
+            },
+            "diagnostics" => {
+                self.show_diagnostics = true;
+                self.diagnostics_scroll = 0;
+            }
+            "cost" => {
+                let cost = &self.session_cost;
+                let usage = &self.token_usage;
+                let mut text = format!(
+                    "Session cost: {} over {} request(s)\n  Duration: {} (API {})\n  Turns: {}\n  Tokens: in {}, out {}, cache read {}, cache write {}",
+                    ui::format_cost(cost.cost_usd),
+                    cost.requests,
+                    ui::format_duration(cost.duration_ms),
+                    ui::format_duration(cost.duration_api_ms),
+                    cost.num_turns,
+                    ui::format_tokens(usage.input_tokens),
+                    ui::format_tokens(usage.output_tokens),
+                    ui::format_tokens(usage.cache_read_tokens),
+                    ui::format_tokens(usage.cache_write_tokens),
+                );
+                if let Some(last) = &cost.last {
+                    text.push_str(&format!(
+                        "\nLast request: {}, {} (API {}), {} turn(s){}",
+                        ui::format_cost(last.cost_usd),
+                        ui::format_duration(last.duration_ms),
+                        ui::format_duration(last.duration_api_ms),
+                        last.num_turns,
+                        if last.is_error { ", ended with error" } else { "" }
+                    ));
+                }
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(text),
+                ));
+            }
+            "permissions" => {
+                self.handle_permissions_command(args);
+            }
+            "budget" => {
+                self.handle_budget_command(args).await?;
+            }
+            "cd" => {
+                if args.trim().is_empty() {
+                    self.status_message =
+                        Some(format!("Working directory: {}", self.working_dir.display()));
+                } else {
+                    match resolve_dir(&self.working_dir, args.trim()) {
+                        Ok(dir) => self.change_dir(dir).await,
+                        Err(e) => self.status_message = Some(format!("cd: {}", e)),
+                    }
+                }
+            }
+            "add-dir" => {
+                if args.trim().is_empty() {
+                    let dirs: Vec<String> = self
+                        .claude_options
+                        .add_dirs
+                        .iter()
+                        .map(|dir| dir.display().to_string())
+                        .collect();
+                    self.status_message = Some(if dirs.is_empty() {
+                        "No extra directories (usage: /add-dir <path>)".to_string()
+                    } else {
+                        format!("Extra directories: {}", dirs.join(", "))
+                    });
+                } else {
+                    match resolve_dir(&self.working_dir, args.trim()) {
+                        Ok(dir) if self.claude_options.add_dirs.contains(&dir) => {
+                            self.status_message = Some(format!("Already added: {}", dir.display()));
+                        }
+                        Ok(dir) => {
+                            self.status_message = Some(format!(
+                                "Added {} (applies to next message)",
+                                dir.display()
+                            ));
+                            self.claude_options.add_dirs.push(dir);
+                            self.restart_claude = true;
+                        }
+                        Err(e) => self.status_message = Some(format!("add-dir: {}", e)),
+                    }
+                }
+            }
+            "sessions" => {
+                let sessions = self.session_manager.list_sessions().await?;
+                let msg = if sessions.is_empty() {
+                    "No other active sessions".to_string()
+                } else {
+                    sessions
+                        .iter()
                         .map(|s| format!("  {} ({}) - {}", s.id, s.cwd, s.task))
                         .collect::<Vec<_>>()
                         .join("\n")
                 };
-                self.messages.push(ConversationEntry {
-                    role: Role::System,
-                    content: ConversationContent::Text(format!("Active sessions:\n{}", msg)),
-                    timestamp: chrono::Utc::now(),
-                });
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(format!("Active sessions:\n{}", msg)),
+                ));
             }
             "send" => {
                 let parts: Vec<&str> = args.splitn(2, ' ').collect();
@@ -467,35 +1505,26 @@ impl App {
                     self.status_message = Some("No messages".to_string());
                 } else {
                     for msg in messages {
-                        self.messages.push(ConversationEntry {
-                            role: Role::System,
-                            content: ConversationContent::Text(format!(
+                        self.messages.push(ConversationEntry::new(
+                            Role::System,
+                            ConversationContent::Text(format!(
                                 "[{}] {}: {}",
                                 msg.time, msg.from, msg.message
                             )),
-                            timestamp: chrono::Utc::now(),
-                        });
+                        ));
                     }
                 }
             }
             "help" => {
-                let help = r#"Commands:
-  !<cmd>         Run bash command
-  /quit          Exit
-  /clear         Clear conversation
-  /model <name>  Set model
-  /sessions      List active sessions
-  /send <id> <m> Send message to session
-  /broadcast <m> Broadcast to all sessions
-  /inbox         Read incoming messages
-  *              Toggle voice recording
-  Ctrl+C         Interrupt Claude
-  Ctrl+Q         Quit"#;
-                self.messages.push(ConversationEntry {
-                    role: Role::System,
-                    content: ConversationContent::Text(help.to_string()),
-                    timestamp: chrono::Utc::now(),
-                });
+                let help = self.help_text();
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(help),
+                ));
+            }
+            // The CLI runs its own and custom commands, arguments and all
+            _ if find_claude_command(&self.claude_commands, command).is_some() => {
+                self.send_to_claude(input).await?;
             }
             _ => {
                 self.status_message = Some(format!("Unknown command: /{}", command));
@@ -504,43 +1533,516 @@ impl App {
         Ok(())
     }
 
+    /// Warn about model names passed to the CLI unchecked
+    fn warn_unknown_models(&mut self) {
+        let options = &self.claude_options;
+        let models = [Some(&options.model), options.fallback_model.as_ref()];
+        let warnings: Vec<String> = models
+            .into_iter()
+            .flatten()
+            .filter_map(|model| validate_model(model).ok().flatten())
+            .collect();
+        for warning in warnings {
+            self.messages.push(ConversationEntry::new(
+                Role::System,
+                ConversationContent::Text(format!("Warning: {}", warning)),
+            ));
+        }
+    }
+
+    /// With --resume or --continue, show the conversation being picked up
+    fn show_resumed_history(&mut self) {
+        let Some(dir) = project_transcripts_dir(&self.working_dir) else {
+            return;
+        };
+        let path = match &self.claude_options.resume_session {
+            Some(id) => dir.join(format!("{}.jsonl", id)),
+            // The CLI continues the most recent session
+            None if self.claude_options.continue_session => match latest_transcript(&dir) {
+                Some(path) => path,
+                None => return,
+            },
+            None => return,
+        };
+        match load_transcript(&path) {
+            Ok(items) => self.show_transcript(items),
+            Err(e) => tracing::debug!("Failed to load transcript: {}", e),
+        }
+    }
+
+    /// Switch to a past session: show its transcript and resume it with the
+    /// next message
+    fn resume_session(&mut self, session_id: &str, path: &Path) {
+        if self.claude_busy {
+            self.status_message = Some("Wait for Claude to finish before resuming".to_string());
+            return;
+        }
+        let items = match load_transcript(path) {
+            Ok(items) => items,
+            Err(e) => {
+                self.status_message = Some(format!("Failed to load session: {}", e));
+                return;
+            }
+        };
+
+        self.messages.clear();
+        self.show_transcript(items);
+        self.clear_saved_entries();
+        self.claude_session = None;
+        self.claude_options.resume_session = Some(session_id.to_string());
+        self.restart_claude = true;
+        self.turn_interrupted = false;
+        // The size is known from the next request
+        self.token_usage.context_tokens = 0;
+        self.scroll_offset = 0;
+        self.status_message = Some(format!("Resumed session {}", session_id));
+    }
+
+    /// Bring back the view a crashed session left: its conversation, draft,
+    /// queue and history. The next message resumes its Claude session.
+    async fn restore(&mut self, saved: SavedState) -> Result<()> {
+        saved.discard();
+        let snapshot = saved.snapshot;
+        self.messages = saved.entries;
+        self.input = snapshot.input;
+        self.cursor_position = snapshot.cursor_position.min(self.input.len());
+        self.message_queue = snapshot.message_queue.into();
+        self.input_history = snapshot.input_history;
+        if let Some(session_id) = snapshot.claude_session_id {
+            self.claude_options.resume_session = Some(session_id);
+            self.claude_session = None;
+            self.restart_claude = true;
+        }
+        // Bash output is routed by id, so new ids must not match restored ones
+        let last_bash_id = self.messages.iter().filter_map(|entry| match entry.content {
+            ConversationContent::BashCommand { id, .. } => Some(id.0),
+            _ => None,
+        });
+        self.last_request_id = last_bash_id.fold(self.last_request_id, u64::max);
+        self.scroll_offset = 0;
+
+        self.clear_saved_entries();
+        self.status_message = Some(format!(
+            "Restored {} entries from the last session",
+            self.messages.len()
+        ));
+        // Messages that were waiting to be sent go now
+        if !self.claude_busy {
+            self.send_queued().await?;
+        }
+        Ok(())
+    }
+
+    /// Save newly finished entries and, if it changed, the draft
+    fn save_state(&mut self) {
+        self.state_saved_at = Instant::now();
+        let Some(store) = &mut self.state_store else {
+            return;
+        };
+        // Entries still changing wait: running tools and bash commands, and
+        // whatever Claude is streaming into
+        let saved = store.saved_entries().min(self.messages.len());
+        let settled = if self.claude_busy {
+            self.messages.len().saturating_sub(1).max(saved)
+        } else {
+            self.messages.len()
+        };
+        let finished = self.messages[saved..settled]
+            .iter()
+            .take_while(|entry| match &entry.content {
+                ConversationContent::ToolUse { status, .. } => *status != ToolStatus::Pending,
+                ConversationContent::BashCommand { exit_code, .. } => exit_code.is_some(),
+                _ => true,
+            })
+            .count();
+        if let Err(e) = store.append(&self.messages[saved..saved + finished]) {
+            tracing::debug!("Failed to save conversation: {}", e);
+        }
+
+        let claude_session_id = self
+            .claude_session
+            .as_ref()
+            .map(|session| session.session_id.clone())
+            .filter(|id| !id.is_empty())
+            .or_else(|| self.claude_options.resume_session.clone());
+        let snapshot = Snapshot {
+            pid: std::process::id(),
+            working_dir: self.working_dir.clone(),
+            claude_session_id,
+            input: self.input.clone(),
+            cursor_position: self.cursor_position,
+            message_queue: self.message_queue.iter().cloned().collect(),
+            input_history: self.input_history.clone(),
+        };
+        if let Err(e) = store.save_snapshot(snapshot) {
+            tracing::debug!("Failed to save draft: {}", e);
+        }
+    }
+
+    /// Start the saved conversation over after it was cleared or replaced
+    fn clear_saved_entries(&mut self) {
+        if let Some(store) = &mut self.state_store {
+            if let Err(e) = store.clear_entries() {
+                tracing::debug!("Failed to clear saved conversation: {}", e);
+            }
+        }
+        self.save_state();
+    }
+
+    /// Rebuild conversation entries from a transcript
+    fn show_transcript(&mut self, items: Vec<TranscriptItem>) {
+        for item in items {
+            match item {
+                TranscriptItem::Prompt(text) => {
+                    self.finish_turn();
+                    self.messages.push(ConversationEntry::new(
+                        Role::User,
+                        ConversationContent::Text(text),
+                    ));
+                }
+                TranscriptItem::Event(event) => self.handle_claude_event(event),
+            }
+        }
+        self.finish_turn();
+        // Calls that never got a result were cut off
+        interrupt_pending_tools(&mut self.messages);
+    }
+
+    /// Commands and keys for /help
+    fn help_text(&self) -> String {
+        let mut help = "Commands:\n  !<cmd>         Run bash command\n".to_string();
+        for (usage, description) in APP_COMMANDS {
+            help.push_str(&format!("  {:<14} {}\n", format!("/{}", usage), description));
+        }
+        help.push_str(
+            "  *              Toggle voice recording
+  Tab            Complete a command
+  Shift+Tab      Cycle permission mode
+  Ctrl+O         Expand/collapse subagents
+  Ctrl+C         Interrupt Claude
+  Ctrl+Q         Quit
+Claude commands:",
+        );
+        for command in &self.claude_commands {
+            let usage = match &command.argument_hint {
+                Some(hint) => format!("/{} {}", command.name, hint),
+                None => format!("/{}", command.name),
+            };
+            help.push_str(&format!("\n  {:<14} {}", usage, command.description));
+            if command.source != CommandSource::Cli {
+                help.push_str(&format!(" ({})", command.origin()));
+            }
+        }
+        help
+    }
+
+    /// Commands matching a slash command being typed
+    fn completions(&self) -> Vec<Completion<'_>> {
+        let Some(prefix) = self.input.strip_prefix('/') else {
+            return Vec::new();
+        };
+        if prefix.contains(char::is_whitespace) {
+            return Vec::new();
+        }
+        let app_commands = APP_COMMANDS.iter().map(|(usage, description)| {
+            let (name, hint) = match usage.split_once(' ') {
+                Some((name, hint)) => (name, Some(hint)),
+                None => (*usage, None),
+            };
+            Completion {
+                name,
+                hint,
+                description,
+                origin: None,
+            }
+        });
+        let claude_commands = self.claude_commands.iter().map(|command| Completion {
+            name: &command.name,
+            hint: command.argument_hint.as_deref(),
+            description: &command.description,
+            origin: Some(command.origin()),
+        });
+        app_commands
+            .chain(claude_commands)
+            .filter(|completion| completion.name.starts_with(prefix))
+            .collect()
+    }
+
+    /// Extend a partly typed command as far as the matches agree
+    fn complete_command(&mut self) {
+        let completions = self.completions();
+        let names: Vec<&str> = completions.iter().map(|completion| completion.name).collect();
+        let completed = match names.as_slice() {
+            [] => return,
+            [name] => format!("/{} ", name),
+            _ => format!("/{}", crate::input_utils::common_prefix(&names)),
+        };
+        self.input = completed;
+        self.cursor_position = self.input.len();
+    }
+
+    /// Describe the MCP servers Claude reported and the configs we passed
+    fn mcp_summary(&self) -> String {
+        let options = &self.claude_options;
+        let mut text = match &self.claude_session {
+            Some(session) => {
+                // The permission bridge is our own plumbing, not a user server
+                let servers: Vec<_> = session
+                    .mcp_servers
+                    .iter()
+                    .filter(|server| server.name != PERMISSION_SERVER_NAME)
+                    .collect();
+                if servers.is_empty() {
+                    "No MCP servers".to_string()
+                } else {
+                    let mut text = "MCP servers:".to_string();
+                    for server in servers {
+                        let tools = mcp_server_tools(&session.tools, &server.name);
+                        text.push_str(&format!("\n  {} ({})", server.name, server.status));
+                        if tools.is_empty() {
+                            text.push_str(" - no tools");
+                        } else {
+                            text.push_str(&format!(
+                                " - {} tool(s): {}",
+                                tools.len(),
+                                tools.join(", ")
+                            ));
+                        }
+                    }
+                    text
+                }
+            }
+            None => "No Claude session yet (send a message to start one)".to_string(),
+        };
+        if !options.mcp_configs.is_empty() {
+            text.push_str(&format!("\nConfig: {}", options.mcp_configs.join(", ")));
+        }
+        if options.strict_mcp_config {
+            text.push_str("\nStrict: only servers from these configs are loaded");
+        }
+        text
+    }
+
+    /// Describe the effective system prompt and where each part comes from
+    fn system_prompt_summary(&self) -> String {
+        let options = &self.claude_options;
+        let mut text = match &options.system_prompt {
+            Some(prompt) => format!("System prompt (--system-prompt):\n{}", prompt),
+            None => "System prompt: Claude Code default".to_string(),
+        };
+        match &options.project_prompt {
+            Some(prompt) => {
+                text.push_str(&format!("\n\nAppended from {}:\n{}", PROJECT_PROMPT_FILE, prompt));
+            }
+            None => {
+                let note = format!("\n\nNo project prompt ({} is missing or empty)", PROJECT_PROMPT_FILE);
+                text.push_str(&note);
+            }
+        }
+        if let Some(prompt) = &options.append_system_prompt {
+            text.push_str(&format!("\n\nAppended from --append-system-prompt:\n{}", prompt));
+        }
+        text.push_str("\n\nUse /system edit to edit the project prompt.");
+        text
+    }
+
+    fn handle_permissions_command(&mut self, args: &str) {
+        let (action, value) = args.split_once(' ').unwrap_or((args, ""));
+        let value = value.trim();
+        let options = &mut self.claude_options;
+
+        match action {
+            "" => {
+                let list = |tools: &[String]| {
+                    if tools.is_empty() {
+                        "(none)".to_string()
+                    } else {
+                        tools.join(", ")
+                    }
+                };
+                let text = format!(
+                    "Permission mode: {}\n  Allowed tools: {}\n  Disallowed tools: {}\n  Project rules: {}\nUsage: /permissions mode <default|plan|acceptEdits|bypassPermissions>\n       /permissions allow|deny <tool>, /permissions reset",
+                    options.permission_mode.as_str(),
+                    list(&options.allowed_tools),
+                    list(&options.disallowed_tools),
+                    list(&self.permission_rules.allow),
+                );
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(text),
+                ));
+            }
+            "mode" => match PermissionMode::parse(value) {
+                Some(mode) => self.set_permission_mode(mode),
+                None => {
+                    self.status_message = Some(
+                        "Usage: /permissions mode <default|plan|acceptEdits|bypassPermissions>".to_string(),
+                    );
+                }
+            },
+            "allow" | "deny" if !value.is_empty() => {
+                let tool = value.to_string();
+                options.allowed_tools.retain(|t| *t != tool);
+                options.disallowed_tools.retain(|t| *t != tool);
+                if action == "allow" {
+                    options.allowed_tools.push(tool.clone());
+                } else {
+                    options.disallowed_tools.push(tool.clone());
+                }
+                let verb = if action == "allow" { "Allowed" } else { "Disallowed" };
+                self.restart_claude = true;
+                self.status_message = Some(format!("{} {} (applies to next message)", verb, tool));
+            }
+            "reset" => {
+                options.allowed_tools.clear();
+                options.disallowed_tools.clear();
+                self.restart_claude = true;
+                self.status_message = Some("Tool lists cleared (applies to next message)".to_string());
+            }
+            _ => {
+                self.status_message =
+                    Some("Usage: /permissions [mode <m> | allow <tool> | deny <tool> | reset]".to_string());
+            }
+        }
+    }
+
+    /// Switch to a validated model; the CLI is respawned before the next message
+    fn set_model(&mut self, name: &str) {
+        let warning = match validate_model(name) {
+            Ok(warning) => warning,
+            Err(e) => {
+                self.status_message = Some(e);
+                return;
+            }
+        };
+        if self.claude_options.fallback_model.as_deref() == Some(name) {
+            self.status_message = Some(format!("{} is already the fallback model", name));
+            return;
+        }
+        if name != self.claude_options.model {
+            self.claude_options.model = name.to_string();
+            self.reported_model = None;
+            self.restart_claude = true;
+        }
+        self.status_message = Some(match warning {
+            Some(warning) => format!("Model set to: {}. {}", name, warning),
+            None => format!("Model set to: {}", name),
+        });
+    }
+
+    /// Change the permission mode; the CLI is respawned before the next message
+    fn set_permission_mode(&mut self, mode: PermissionMode) {
+        if mode != self.claude_options.permission_mode {
+            self.claude_options.permission_mode = mode;
+            self.restart_claude = true;
+        }
+        self.status_message = Some(format!(
+            "Permission mode: {} (applies to next message)",
+            mode.as_str()
+        ));
+    }
+
     async fn send_to_claude(&mut self, message: &str) -> Result<()> {
-        // If Claude is busy, queue the message
+        if self.replay_id.is_some() {
+            self.status_message = Some("Replay mode: messages are not sent to Claude".to_string());
+            return Ok(());
+        }
+
+        // Past a budget limit, hold the message until the budget is raised
+        if self.budget_exceeded.is_some() {
+            self.message_queue.push_back(message.to_string());
+            self.status_message = Some(format!(
+                "Budget reached: {} message(s) held, raise it with /budget",
+                self.message_queue.len()
+            ));
+            return Ok(());
+        }
+
+        // If Claude is busy, queue the message. Writing it to the live
+        // process wouldn't steer the running turn, since the CLI takes
+        // stream-json messages one turn at a time; holding it here keeps the
+        // budget check, bash context and interrupt handling per turn.
         if self.claude_busy {
-            self.message_queue.push(message.to_string());
+            self.message_queue.push_back(message.to_string());
             self.status_message = Some(format!("Queued ({} pending)", self.message_queue.len()));
             return Ok(());
         }
 
         // Add user message to conversation
-        self.messages.push(ConversationEntry {
-            role: Role::User,
-            content: ConversationContent::Text(message.to_string()),
-            timestamp: chrono::Utc::now(),
-        });
+        self.messages.push(ConversationEntry::new(
+            Role::User,
+            ConversationContent::Text(message.to_string()),
+        ));
 
         // Build context from recent bash commands
         let context = self.build_context();
 
-        // Start Claude process
         self.claude_busy = true;
         self.streaming_buffer.clear();
 
-        let mut process = ClaudeProcess::new(
-            &self.model,
-            self.message_tx.clone(),
-            self.continue_session,
-            self.resume_session.take(),
-        )?;
+        if self.restart_claude {
+            self.claude_process = None;
+            self.restart_claude = false;
+        }
 
-        let full_message = if context.is_empty() {
-            message.to_string()
-        } else {
-            format!("{}\n\n{}", context, message)
+        // Start the Claude process on first use; later turns reuse it
+        let process = match self.claude_process.take() {
+            Some(process) => process,
+            None => {
+                // Resume the exact session the CLI reported, if we have one
+                let resume = self
+                    .claude_session
+                    .as_ref()
+                    .map(|s| s.session_id.clone())
+                    .filter(|id| !id.is_empty())
+                    .or_else(|| self.claude_options.resume_session.take());
+                let mut options = self.claude_options.clone();
+                options.resume_session = resume;
+                let id = self.next_request_id();
+                let recorder = self.stream_recorder.clone();
+                let tx = self.message_tx.clone();
+                let process = match ClaudeProcess::new(id, &options, recorder, tx) {
+                    Ok(process) => process,
+                    Err(e) => {
+                        self.claude_busy = false;
+                        self.diagnostics.last_error = Some(e.to_string());
+                        self.messages.push(ConversationEntry::new(
+                            Role::System,
+                            ConversationContent::Text(format!("Error: {}", e)),
+                        ));
+                        return Ok(());
+                    }
+                };
+                // Any respawn picks up this conversation again
+                self.claude_options.continue_session = true;
+                process
+            }
         };
+        let process = self.claude_process.insert(process);
+
+        // CLI commands such as /compact must arrive as typed; the notes
+        // wait for the next message
+        let is_command = message.starts_with('/');
+        let mut full_message = String::new();
+        if !is_command && std::mem::take(&mut self.turn_interrupted) {
+            full_message.push_str(INTERRUPTED_NOTE);
+            full_message.push_str("\n\n");
+        }
+        if !is_command && !context.is_empty() {
+            full_message.push_str(&context);
+            full_message.push_str("\n\n");
+        }
+        full_message.push_str(message);
 
-        process.send(&full_message).await?;
-        self.claude_process = Some(process);
+        if let Err(e) = process.send(&full_message).await {
+            self.claude_process = None;
+            self.claude_busy = false;
+            self.messages.push(ConversationEntry::new(
+                Role::System,
+                ConversationContent::Text(format!("Error: {}", e)),
+            ));
+            return Ok(());
+        }
 
         // Reset scroll to see new messages
         self.scroll_offset = 0;
@@ -559,7 +2061,8 @@ impl App {
                 ConversationContent::BashCommand {
                     command,
                     output,
-                    exit_code,
+                    exit_code: Some(exit_code),
+                    ..
                 } => Some(format!(
                     "$ {}\n{}\n(exit code: {})",
                     command, output, exit_code
@@ -581,12 +2084,16 @@ impl App {
     async fn toggle_voice_recording(&mut self) -> Result<()> {
         match self.input_mode {
             InputMode::Normal => {
-                self.voice_recorder.start().await?;
+                let id = self.next_request_id();
+                self.voice_job = Some(id);
+                self.voice_recorder.start(id).await?;
                 self.input_mode = InputMode::Recording;
                 self.status_message = Some("Recording...".to_string());
             }
             InputMode::Recording => {
-                self.voice_recorder.stop().await?;
+                if let Some(id) = self.voice_job {
+                    self.voice_recorder.stop(id).await?;
+                }
                 self.input_mode = InputMode::Normal;
                 self.status_message = Some("Transcribing...".to_string());
             }
@@ -632,121 +2139,343 @@ impl App {
         self.cursor_position = new_pos;
     }
 
-    async fn handle_app_message(&mut self, msg: AppMessage) -> Result<()> {
+    pub(crate) async fn handle_app_message(&mut self, msg: AppMessage) -> Result<()> {
         match msg {
-            AppMessage::ClaudeEvent(event) => {
+            AppMessage::ClaudeEvent(id, event) => {
+                if !self.is_current_claude(id) {
+                    return Ok(());
+                }
                 self.handle_claude_event(event);
+                self.check_budgets();
+                if !self.claude_busy {
+                    self.send_queued().await?;
+                }
             }
-            AppMessage::ClaudeFinished => {
-                self.claude_busy = false;
+            AppMessage::ClaudeFinished(id) => {
+                if !self.is_current_claude(id) {
+                    return Ok(());
+                }
+                // The process exited; the next message spawns a new one.
+                // Tool calls it didn't finish never will.
                 self.claude_process = None;
-
-                // Finalize streaming buffer
-                if !self.streaming_buffer.is_empty() {
-                    self.messages.push(ConversationEntry {
-                        role: Role::Assistant,
-                        content: ConversationContent::Text(std::mem::take(&mut self.streaming_buffer)),
-                        timestamp: chrono::Utc::now(),
-                    });
+                self.pending_permissions.clear();
+                interrupt_pending_tools(&mut self.messages);
+                self.finish_turn();
+                if self.replay_id.is_some() {
+                    self.status_message = Some("Replay finished".to_string());
                 }
-
-                // Process queued messages
-                if let Some(queued) = self.message_queue.pop() {
-                    self.status_message = Some(format!("{} more queued", self.message_queue.len()));
-                    // Use Box::pin to allow recursion in async
-                    Box::pin(self.send_to_claude(&queued)).await?;
+                self.send_queued().await?;
+            }
+            AppMessage::ClaudeError(id, err) => {
+                self.diagnostics.last_error = Some(err.clone());
+                if !self.is_current_claude(id) {
+                    return Ok(());
                 }
+                // The ClaudeFinished that follows closes the turn; the
+                // partial reply goes above the error
+                self.commit_streaming_buffer();
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(format!("Error: {}", err)),
+                ));
             }
-            AppMessage::ClaudeError(err) => {
-                self.claude_busy = false;
-                self.claude_process = None;
-                self.messages.push(ConversationEntry {
-                    role: Role::System,
-                    content: ConversationContent::Text(format!("Error: {}", err)),
-                    timestamp: chrono::Utc::now(),
-                });
+            AppMessage::ClaudeStderr(line) => {
+                if self.diagnostics.stderr.len() == STDERR_HISTORY_LINES {
+                    self.diagnostics.stderr.pop_front();
+                }
+                self.diagnostics.stderr.push_back(line);
             }
-            AppMessage::BashOutput(output) => {
-                // Update the last bash entry with output
-                if let Some(entry) = self.messages.last_mut() {
-                    if let ConversationContent::Text(text) = &entry.content {
-                        if text.starts_with("$ ") {
-                            let command = text[2..].to_string();
-                            entry.content = ConversationContent::BashCommand {
-                                command,
-                                output,
-                                exit_code: 0,
-                            };
-                        }
-                    }
+            AppMessage::Preflight(report) => {
+                for problem in &report.problems {
+                    self.messages.push(ConversationEntry::new(
+                        Role::System,
+                        ConversationContent::Text(format!("Warning: {}", problem)),
+                    ));
                 }
+                if !report.problems.is_empty() {
+                    self.status_message = Some("Claude CLI check failed (see /diagnostics)".to_string());
+                }
+                self.diagnostics.preflight = Some(report);
             }
-            AppMessage::BashFinished(exit_code) => {
-                // Update exit code
-                if let Some(entry) = self.messages.last_mut() {
-                    if let ConversationContent::BashCommand {
-                        exit_code: ref mut ec,
-                        ..
-                    } = entry.content
-                    {
-                        *ec = exit_code;
-                    }
+            AppMessage::BashOutput(id, output) => {
+                if let Some(ConversationContent::BashCommand { output: text, .. }) =
+                    self.find_bash_command(id)
+                {
+                    *text = output;
                 }
             }
-            AppMessage::VoiceTranscription(text) => {
+            AppMessage::BashFinished(id, exit_code) => {
+                if let Some(ConversationContent::BashCommand { exit_code: code, .. }) =
+                    self.find_bash_command(id)
+                {
+                    *code = Some(exit_code);
+                }
+            }
+            AppMessage::VoiceTranscription(id, text) => {
+                // Results of cancelled recordings are dropped
+                if self.voice_job != Some(id) {
+                    return Ok(());
+                }
+                self.voice_job = None;
                 // Insert transcription into input
                 self.input.push_str(&text);
                 self.cursor_position = self.input.len();
                 self.status_message = Some("Transcription complete".to_string());
             }
-            AppMessage::VoiceError(err) => {
+            AppMessage::VoiceError(id, err) => {
+                if self.voice_job != Some(id) {
+                    return Ok(());
+                }
+                self.voice_job = None;
                 self.input_mode = InputMode::Normal;
                 self.status_message = Some(format!("Voice error: {}", err));
             }
+            AppMessage::PermissionRequest(request) => {
+                if self.permission_rules.allows(&request.tool_name, &request.input) {
+                    let _ = request.responder.send(PermissionDecision::Allow);
+                } else {
+                    if self.pending_permissions.is_empty() {
+                        self.permission_shown_at = Instant::now();
+                    }
+                    self.pending_permissions.push_back(request);
+                }
+            }
+            AppMessage::TranscriptsListed(dir, sessions) => {
+                // Stale if /cd moved to another project meanwhile
+                if project_transcripts_dir(&self.working_dir) != Some(dir) {
+                    return Ok(());
+                }
+                if sessions.is_empty() {
+                    self.status_message =
+                        Some(format!("No past sessions for {}", self.working_dir.display()));
+                } else {
+                    self.status_message = None;
+                    self.resume_sessions = sessions;
+                    self.resume_picker = Some(0);
+                }
+            }
+            AppMessage::ReplayInput(id, text) => {
+                if !self.is_current_claude(id) {
+                    return Ok(());
+                }
+                self.finish_turn();
+                self.messages.push(ConversationEntry::new(
+                    Role::User,
+                    ConversationContent::Text(text),
+                ));
+                self.claude_busy = true;
+                self.scroll_offset = 0;
+            }
             AppMessage::SessionMessage { from, message } => {
-                self.messages.push(ConversationEntry {
-                    role: Role::System,
-                    content: ConversationContent::Text(format!("[Session {}]: {}", from, message)),
-                    timestamp: chrono::Utc::now(),
-                });
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(format!("[Session {}]: {}", from, message)),
+                ));
             }
         }
         Ok(())
     }
 
+    /// The content of the bash command entry started with `id`
+    fn find_bash_command(&mut self, id: RequestId) -> Option<&mut ConversationContent> {
+        self.messages
+            .iter_mut()
+            .rev()
+            .map(|entry| &mut entry.content)
+            .find(|content| {
+                matches!(content, ConversationContent::BashCommand { id: bash_id, .. } if *bash_id == id)
+            })
+    }
+
+    /// Mark the current turn as done and finalize the streaming buffer
+    fn finish_turn(&mut self) {
+        self.claude_busy = false;
+        self.commit_streaming_buffer();
+    }
+
+    /// Move streamed text into the conversation
+    fn commit_streaming_buffer(&mut self) {
+        if !self.streaming_buffer.is_empty() {
+            self.messages.push(ConversationEntry::new(
+                Role::Assistant,
+                ConversationContent::Text(std::mem::take(&mut self.streaming_buffer)),
+            ));
+        }
+    }
+
+    /// Stop the running Claude turn, keeping what it has produced so far
+    fn interrupt_claude(&mut self) {
+        // Dropping pending prompts denies them
+        self.pending_permissions.clear();
+        if let Some(mut process) = self.claude_process.take() {
+            process.interrupt();
+            self.interrupt_turn();
+        }
+    }
+
+    /// Close the current turn after an interrupt, keeping the partial answer
+    /// and marking unfinished tool calls
+    fn interrupt_turn(&mut self) {
+        self.claude_busy = false;
+        self.turn_interrupted = true;
+        interrupt_pending_tools(&mut self.messages);
+
+        let partial = std::mem::take(&mut self.streaming_buffer);
+        let entry = if partial.trim().is_empty() {
+            let marker = ConversationContent::Text("[interrupted]".to_string());
+            ConversationEntry::new(Role::System, marker)
+        } else {
+            ConversationEntry::new(
+                Role::Assistant,
+                ConversationContent::Text(format!("{}\n[interrupted]", partial.trim_end())),
+            )
+        };
+        self.messages.push(entry);
+        self.status_message = Some("Interrupted".to_string());
+    }
+
+    /// Send the next queued message, if any
+    async fn send_queued(&mut self) -> Result<()> {
+        if self.budget_exceeded.is_some() {
+            return Ok(());
+        }
+        if let Some(queued) = self.message_queue.pop_front() {
+            self.status_message = Some(format!("{} more queued", self.message_queue.len()));
+            // Use Box::pin to allow recursion in async
+            Box::pin(self.send_to_claude(&queued)).await?;
+        }
+        Ok(())
+    }
+
+    /// Add a subagent's output under the Task call that spawned it
+    fn handle_subagent_event(&mut self, parent_tool_use_id: &str, event: StreamEvent) {
+        let Some(parent) = find_tool_use(&mut self.messages, parent_tool_use_id) else {
+            // Unknown parent: show it in the main conversation
+            self.handle_claude_event(event);
+            return;
+        };
+        let children = &mut parent.children;
+
+        // Subagent output arrives as whole messages, not deltas, so each
+        // one is its own entry
+        match event {
+            StreamEvent::Text(text) => {
+                let content = ConversationContent::Text(text);
+                children.push(ConversationEntry::new(Role::Assistant, content));
+            }
+            StreamEvent::Thinking(text) => {
+                let content = ConversationContent::Thinking(text);
+                children.push(ConversationEntry::new(Role::Assistant, content));
+            }
+            StreamEvent::ToolUse { id, name, input } => {
+                children.push(ConversationEntry::new(
+                    Role::Tool,
+                    ConversationContent::ToolUse {
+                        id,
+                        name,
+                        input,
+                        status: ToolStatus::Pending,
+                        result: None,
+                        finished_at: None,
+                    },
+                ));
+            }
+            // Subagents have their own context window; only the session
+            // totals count their usage
+            usage @ StreamEvent::Usage { .. } => {
+                let context = self.token_usage.context_tokens;
+                self.handle_claude_event(usage);
+                self.token_usage.context_tokens = context;
+            }
+            // Results and input updates find their tool call by id
+            other => self.handle_claude_event(other),
+        }
+    }
+
     fn handle_claude_event(&mut self, event: StreamEvent) {
         match event {
+            StreamEvent::Subagent {
+                parent_tool_use_id,
+                event,
+            } => {
+                self.handle_subagent_event(&parent_tool_use_id, *event);
+            }
             StreamEvent::Text(text) => {
                 self.streaming_buffer.push_str(&text);
             }
-            StreamEvent::ToolUse { name, input } => {
+            StreamEvent::ToolUse { id, name, input } => {
                 // Finalize any pending text
                 if !self.streaming_buffer.is_empty() {
-                    self.messages.push(ConversationEntry {
-                        role: Role::Assistant,
-                        content: ConversationContent::Text(std::mem::take(&mut self.streaming_buffer)),
-                        timestamp: chrono::Utc::now(),
+                    self.messages.push(ConversationEntry::new(
+                        Role::Assistant,
+                        ConversationContent::Text(std::mem::take(&mut self.streaming_buffer)),
+                    ));
+                }
+                self.messages.push(ConversationEntry::new(
+                    Role::Tool,
+                    ConversationContent::ToolUse {
+                        id,
+                        name,
+                        input,
+                        status: ToolStatus::Pending,
+                        result: None,
+                        finished_at: None,
+                    },
+                ));
+            }
+            StreamEvent::ToolResult {
+                id,
+                name,
+                result,
+                is_error,
+            } => {
+                // Attach the result to its invocation when we know it
+                let tool_call =
+                    find_tool_use(&mut self.messages, &id).and_then(|entry| match &mut entry.content {
+                        ConversationContent::ToolUse {
+                            status,
+                            result,
+                            finished_at,
+                            ..
+                        } => Some((status, result, finished_at)),
+                        _ => None,
                     });
+
+                match tool_call {
+                    Some((status, tool_result, finished_at)) => {
+                        *status = if is_error { ToolStatus::Error } else { ToolStatus::Success };
+                        *tool_result = Some(result);
+                        *finished_at = Some(chrono::Utc::now());
+                    }
+                    None => {
+                        self.messages.push(ConversationEntry::new(
+                            Role::Tool,
+                            ConversationContent::ToolResult { name, result },
+                        ));
+                    }
                 }
-                self.messages.push(ConversationEntry {
-                    role: Role::Tool,
-                    content: ConversationContent::ToolUse { name, input },
-                    timestamp: chrono::Utc::now(),
-                });
             }
-            StreamEvent::ToolResult { name, result } => {
-                self.messages.push(ConversationEntry {
-                    role: Role::Tool,
-                    content: ConversationContent::ToolResult { name, result },
-                    timestamp: chrono::Utc::now(),
-                });
+            StreamEvent::ToolInput { id, input } => {
+                if let Some(entry) = find_tool_use(&mut self.messages, &id) {
+                    if let ConversationContent::ToolUse { input: tool_input, .. } = &mut entry.content {
+                        *tool_input = input;
+                    }
+                }
             }
             StreamEvent::Thinking(text) => {
-                self.messages.push(ConversationEntry {
-                    role: Role::Assistant,
-                    content: ConversationContent::Thinking(text),
-                    timestamp: chrono::Utc::now(),
-                });
+                // Thinking deltas extend the block being streamed
+                if let Some(ConversationEntry {
+                    content: ConversationContent::Thinking(thinking),
+                    ..
+                }) = self.messages.last_mut()
+                {
+                    thinking.push_str(&text);
+                    return;
+                }
+                self.messages.push(ConversationEntry::new(
+                    Role::Assistant,
+                    ConversationContent::Thinking(text),
+                ));
             }
             StreamEvent::Usage {
                 input_tokens,
@@ -758,11 +2487,67 @@ impl App {
                 self.token_usage.output_tokens += output_tokens;
                 self.token_usage.cache_read_tokens += cache_read_tokens;
                 self.token_usage.cache_write_tokens += cache_write_tokens;
+                // Output-only updates don't say how big the prompt was
+                let context = input_tokens + cache_read_tokens + cache_write_tokens;
+                if context > 0 {
+                    self.token_usage.context_tokens = context;
+                }
+            }
+            StreamEvent::Compacted {
+                trigger,
+                pre_tokens,
+            } => {
+                // The new size is known from the next request
+                self.token_usage.context_tokens = 0;
+                let how = if trigger == "auto" { "automatically" } else { "on request" };
+                let text = match pre_tokens {
+                    Some(tokens) => format!(
+                        "Conversation compacted {} (was {} tokens)",
+                        how,
+                        format_tokens(tokens)
+                    ),
+                    None => format!("Conversation compacted {}", how),
+                };
+                self.messages.push(ConversationEntry::new(
+                    Role::System,
+                    ConversationContent::Text(text),
+                ));
+            }
+            StreamEvent::SessionInit(init) => {
+                self.reported_model = Some(init.model.clone()).filter(|model| !model.is_empty());
+                self.claude_session = Some(init);
+            }
+            StreamEvent::TurnComplete {
+                cost_usd,
+                duration_ms,
+                duration_api_ms,
+                num_turns,
+                is_error,
+            } => {
+                self.finish_turn();
+                if is_error {
+                    self.status_message = Some("Request ended with an error".to_string());
+                }
+                self.session_cost.add(TurnCost {
+                    cost_usd,
+                    duration_ms,
+                    duration_api_ms,
+                    num_turns,
+                    is_error,
+                });
+                self.save_project_spend();
+                self.save_state();
+                self.check_context();
             }
         }
     }
 
     fn cleanup(&mut self) -> Result<()> {
+        // A clean exit leaves nothing to restore
+        if let Some(store) = self.state_store.take() {
+            store.remove();
+        }
+
         // Deregister session
         if let Some(session_id) = &self.session_id {
             // Blocking cleanup since we're exiting
@@ -772,15 +2557,118 @@ impl App {
                 session_id
             ));
         }
-
-        // Restore terminal
-        disable_raw_mode()?;
-        execute!(
-            self.terminal.backend_mut(),
-            LeaveAlternateScreen,
-            SetCursorStyle::DefaultUserShape
-        )?;
-        self.terminal.show_cursor()?;
         Ok(())
     }
 }
+
+/// Switch the real terminal to raw mode and the alternate screen
+pub fn setup_terminal() -> Result<Terminal<CrosstermBackend<Stdout>>> {
+    enable_raw_mode()?;
+    let mut stdout = io::stdout();
+    execute!(stdout, EnterAlternateScreen, SetCursorStyle::SteadyBar)?;
+    Ok(Terminal::new(CrosstermBackend::new(stdout))?)
+}
+
+/// Set a budget limit from a /budget argument; `off` clears it. Returns
+/// false if the value doesn't parse.
+fn set_limit<T>(limit: &mut Option<T>, value: &str, parse: impl Fn(&str) -> Option<T>) -> bool {
+    if value == "off" {
+        *limit = None;
+        return true;
+    }
+    match parse(value) {
+        Some(value) => {
+            *limit = Some(value);
+            true
+        }
+        None => false,
+    }
+}
+
+/// Resolve a /cd or /add-dir argument against `base`, expanding `~`
+fn resolve_dir(base: &Path, arg: &str) -> Result<PathBuf> {
+    let path = resolve_path(base, arg)?;
+    let dir = path
+        .canonicalize()
+        .map_err(|e| anyhow::anyhow!("{}: {}", path.display(), e))?;
+    if !dir.is_dir() {
+        anyhow::bail!("{} is not a directory", dir.display());
+    }
+    Ok(dir)
+}
+
+/// `arg` relative to `base`, with a leading `~` for the home directory
+fn resolve_path(base: &Path, arg: &str) -> Result<PathBuf> {
+    match arg.strip_prefix('~') {
+        Some(rest) if rest.is_empty() || rest.starts_with('/') => {
+            let home = dirs::home_dir().ok_or_else(|| anyhow::anyhow!("no home directory"))?;
+            Ok(home.join(rest.trim_start_matches('/')))
+        }
+        _ => Ok(base.join(arg)),
+    }
+}
+
+/// Run `$VISUAL` or `$EDITOR` (default `vi`) on a file and wait for it
+async fn run_editor(path: &Path) -> Result<()> {
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    // Editors are often configured with flags, e.g. "code --wait"
+    let mut parts = editor.split_whitespace();
+    let program = parts.next().unwrap_or("vi");
+    let status = tokio::process::Command::new(program)
+        .args(parts)
+        .arg(path)
+        .status()
+        .await?;
+    if !status.success() {
+        anyhow::bail!("{} exited with {}", program, status);
+    }
+    Ok(())
+}
+
+/// Put the terminal back the way we found it
+pub fn restore_terminal(terminal: &mut Terminal<CrosstermBackend<Stdout>>) -> Result<()> {
+    disable_raw_mode()?;
+    execute!(
+        terminal.backend_mut(),
+        LeaveAlternateScreen,
+        SetCursorStyle::DefaultUserShape
+    )?;
+    terminal.show_cursor()?;
+    Ok(())
+}
+
+/// Mark every running tool call, including subagent calls, as interrupted
+fn interrupt_pending_tools(entries: &mut [ConversationEntry]) {
+    let now = chrono::Utc::now();
+    for entry in entries {
+        if let ConversationContent::ToolUse {
+            status, finished_at, ..
+        } = &mut entry.content
+        {
+            if *status == ToolStatus::Pending {
+                *status = ToolStatus::Interrupted;
+                *finished_at = Some(now);
+            }
+        }
+        interrupt_pending_tools(&mut entry.children);
+    }
+}
+
+/// Find the tool call entry with the given tool_use id, including calls
+/// made by subagents
+fn find_tool_use<'a>(entries: &'a mut [ConversationEntry], id: &str) -> Option<&'a mut ConversationEntry> {
+    if id.is_empty() {
+        return None;
+    }
+    for entry in entries.iter_mut().rev() {
+        if matches!(&entry.content, ConversationContent::ToolUse { id: tool_id, .. } if tool_id == id) {
+            return Some(entry);
+        }
+        if let Some(found) = find_tool_use(&mut entry.children, id) {
+            return Some(found);
+        }
+    }
+    None
+}
@@ -0,0 +1,772 @@
+//! End-to-end tests driving the app against a mock Claude CLI
+
+#[cfg(all(test, unix))]
+mod tests {
+    use std::os::unix::fs::PermissionsExt;
+    use std::path::PathBuf;
+    use std::time::{Duration, Instant};
+
+    use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+    use ratatui::{backend::TestBackend, Terminal};
+
+    use crate::app::{App, AppMessage, ConversationContent, ConversationEntry, RequestId, Role};
+    use crate::claude::ClaudeOptions;
+    use crate::permissions::{PermissionDecision, PermissionRequest};
+    use crate::store::{SavedState, Snapshot, StateStore};
+
+    const MOCK_CLAUDE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/mock-claude.sh");
+
+    /// An app wired to the mock CLI, rendering into a test backend
+    struct Harness {
+        app: App,
+        terminal: Terminal<TestBackend>,
+        dir: PathBuf,
+        /// Removes `dir` once the app is gone
+        _temp: tempfile::TempDir,
+    }
+
+    impl Harness {
+        /// Start an app whose Claude plays `scenario` (see mock-claude.sh)
+        fn new(scenario: &str) -> Self {
+            Self::with_options(scenario, |_| {})
+        }
+
+        /// Like `new`, adjusting the Claude options first
+        fn with_options(scenario: &str, configure: impl FnOnce(&mut ClaudeOptions)) -> Self {
+            let temp = tempfile::Builder::new().prefix("claude-terminal-test-").tempdir().unwrap();
+            let dir = temp.path().to_path_buf();
+            let scenario_path = dir.join("scenario.jsonl");
+            std::fs::write(&scenario_path, scenario).unwrap();
+
+            // Each test gets its own wrapper so scenarios don't share env vars
+            let bin = dir.join("claude");
+            std::fs::write(
+                &bin,
+                format!(
+                    "#!/bin/sh\nMOCK_CLAUDE_SCENARIO='{}' MOCK_CLAUDE_LOG='{}' exec sh '{}' \"$@\"\n",
+                    scenario_path.display(),
+                    dir.join("stdin.log").display(),
+                    MOCK_CLAUDE
+                ),
+            )
+            .unwrap();
+            std::fs::set_permissions(&bin, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+            let mut options = ClaudeOptions {
+                claude_bin: Some(bin),
+                model: "sonnet".to_string(),
+                ..Default::default()
+            };
+            configure(&mut options);
+            Self {
+                app: App::new(options).unwrap(),
+                terminal: Terminal::new(TestBackend::new(100, 40)).unwrap(),
+                dir,
+                _temp: temp,
+            }
+        }
+
+        async fn key(&mut self, modifiers: KeyModifiers, code: KeyCode) {
+            self.app.handle_key_event(KeyEvent::new(code, modifiers)).await.unwrap();
+        }
+
+        /// Type a line into the input box and press Enter
+        async fn submit(&mut self, text: &str) {
+            for c in text.chars() {
+                self.key(KeyModifiers::NONE, KeyCode::Char(c)).await;
+            }
+            self.key(KeyModifiers::NONE, KeyCode::Enter).await;
+        }
+
+        /// Render a frame and return it as text
+        fn screen(&mut self) -> String {
+            self.app.draw(&mut self.terminal).unwrap();
+            let buffer = self.terminal.backend().buffer();
+            let width = buffer.area.width as usize;
+            buffer
+                .content
+                .chunks(width)
+                .map(|row| row.iter().map(|cell| cell.symbol()).collect::<String>())
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+
+        /// Handle app messages until `text` is on screen
+        async fn wait_for(&mut self, text: &str) -> String {
+            self.wait_until(text, |screen| screen.contains(text)).await
+        }
+
+        /// Handle app messages until the screen satisfies `done`
+        async fn wait_until(&mut self, what: &str, done: impl Fn(&str) -> bool) -> String {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            loop {
+                let screen = self.screen();
+                if done(&screen) {
+                    return screen;
+                }
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                match tokio::time::timeout(remaining, self.app.next_message()).await {
+                    Ok(Some(msg)) => self.app.handle_app_message(msg).await.unwrap(),
+                    _ => panic!("Timed out waiting for {}; screen:\n{}", what, screen),
+                }
+            }
+        }
+
+        /// Handle whatever app messages arrive during `duration`
+        async fn settle(&mut self, duration: Duration) {
+            let deadline = tokio::time::Instant::now() + duration;
+            while let Ok(Some(msg)) =
+                tokio::time::timeout_at(deadline, self.app.next_message()).await
+            {
+                self.app.handle_app_message(msg).await.unwrap();
+            }
+        }
+
+        /// Wait until the mock CLI has read `text` from stdin
+        async fn wait_for_log(&self, text: &str) {
+            let deadline = Instant::now() + Duration::from_secs(5);
+            while !self.stdin_log().contains(text) {
+                assert!(Instant::now() < deadline, "Timed out waiting for {:?} in stdin", text);
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        }
+
+        /// Everything the mock CLI read from stdin
+        fn stdin_log(&self) -> String {
+            std::fs::read_to_string(self.dir.join("stdin.log")).unwrap_or_default()
+        }
+    }
+
+    const RESULT: &str = r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":10,"num_turns":1,"total_cost_usd":0.01}"#;
+
+    fn reply(text: &str) -> String {
+        format!(
+            r#"{{"type":"assistant","message":{{"id":"msg_{}","content":[{{"type":"text","text":"{}"}}]}}}}"#,
+            uuid::Uuid::new_v4().simple(),
+            text
+        )
+    }
+
+    #[tokio::test]
+    async fn test_reply_rendered() {
+        let mut h = Harness::new(&format!("{}\n{}\n", reply("Hello from mock"), RESULT));
+
+        h.submit("hi").await;
+        let screen = h.wait_for("Hello from mock").await;
+
+        assert!(screen.contains("[You]"));
+        assert!(screen.contains("hi"));
+        assert!(h.stdin_log().contains(r#""text":"hi""#));
+        assert!(h.stdin_log().contains("--output-format stream-json"));
+    }
+
+    #[tokio::test]
+    async fn test_tool_call_rendered_with_result() {
+        let scenario = [
+            r#"{"type":"assistant","message":{"id":"msg_1","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"ls"}}]}}"#.to_string(),
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"a.txt\nb.txt"}]}}"#.to_string(),
+            reply("Two files"),
+            RESULT.to_string(),
+        ]
+        .join("\n");
+        let mut h = Harness::new(&scenario);
+
+        h.submit("list files").await;
+        let screen = h.wait_for("Two files").await;
+
+        assert!(screen.contains("[Tool] Bash  done"));
+        assert!(screen.contains("command: ls"));
+        assert!(screen.contains("> a.txt"));
+        assert!(screen.contains("> b.txt"));
+    }
+
+    #[tokio::test]
+    async fn test_code_highlighted_with_line_numbers() {
+        let scenario = [
+            r#"{"type":"assistant","message":{"id":"msg_1","content":[{"type":"tool_use","id":"toolu_1","name":"Read","input":{"file_path":"src/lib.rs"}}]}}"#.to_string(),
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"     9→pub fn add() {}\n    10→"}]}}"#.to_string(),
+            reply("Call it:\\n\\n```python\\nprint(add())\\n```"),
+            RESULT.to_string(),
+        ]
+        .join("\n");
+        let mut h = Harness::new(&scenario);
+        h.app.set_line_numbers(true);
+
+        h.submit("show lib").await;
+        let screen = h.wait_for("print(add())").await;
+
+        // The file keeps its own line numbers
+        assert!(screen.contains("Rust"));
+        assert!(screen.contains(" 9 │ pub fn add() {}"));
+        assert!(screen.contains("10 │"));
+        assert!(screen.contains("Python"));
+        assert!(screen.contains("1 │ print(add())"));
+        assert!(!screen.contains("```"));
+    }
+
+    #[tokio::test]
+    async fn test_message_queued_while_busy() {
+        let scenario = format!(
+            "sleep 0.3\n{}\n{}\n---\n{}\n{}\n",
+            reply("first reply"),
+            RESULT,
+            reply("second reply"),
+            RESULT
+        );
+        let mut h = Harness::new(&scenario);
+
+        h.submit("first").await;
+        h.submit("second").await;
+        assert!(h.screen().contains("Queued (1 pending)"));
+
+        h.wait_for("first reply").await;
+        h.wait_for("second reply").await;
+        let log = h.stdin_log();
+        let first = log.find(r#""text":"first""#).expect("first message sent");
+        let second = log.find(r#""text":"second""#).expect("second message sent");
+        assert!(first < second);
+    }
+
+    #[tokio::test]
+    async fn test_interrupt_keeps_partial_answer() {
+        let scenario = [
+            r#"{"type":"stream_event","event":{"type":"message_start","message":{"id":"msg_1","content":[]}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"partial answer"}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"Read","input":{}}}}"#,
+            "sleep 5",
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"too late"}}}"#,
+        ]
+        .join("\n");
+        let mut h = Harness::new(&scenario);
+
+        h.submit("slow").await;
+        h.wait_for("[Tool] Read  running").await;
+        h.key(KeyModifiers::CONTROL, KeyCode::Char('c')).await;
+
+        let screen = h.wait_for("Interrupted").await;
+        assert!(screen.contains("partial answer"));
+        assert!(screen.contains("[interrupted]"));
+        assert!(screen.contains("[Tool] Read  interrupted"));
+        assert!(!screen.contains("too late"));
+
+        // Not busy any more, so the next message is sent rather than queued,
+        // and it tells Claude about the interrupt
+        h.submit("again").await;
+        assert!(!h.screen().contains("Queued"));
+        h.wait_for_log("interrupted your previous response").await;
+    }
+
+    #[tokio::test]
+    async fn test_bash_context_sent_with_message() {
+        let mut h = Harness::new(&format!("{}\n{}\n", reply("Saw it"), RESULT));
+
+        h.submit("!echo bash-output-$((1 + 1))").await;
+        h.wait_for("bash-output-2").await;
+        h.wait_until("bash to finish", |screen| !screen.contains("running...")).await;
+        h.submit("what happened?").await;
+        h.wait_for("Saw it").await;
+
+        let log = h.stdin_log();
+        assert!(log.contains("[Recent terminal activity]"));
+        assert!(log.contains("bash-output-2"));
+        assert!(log.contains("what happened?"));
+    }
+
+    #[tokio::test]
+    async fn test_failed_exit_shows_stderr() {
+        let mut h = Harness::new("stderr Error: Invalid API key\nexit 3\n");
+
+        h.submit("hi").await;
+        let screen = h.wait_for("exited with code 3").await;
+        assert!(screen.contains("Error: Invalid API key"));
+
+        h.submit("/diagnostics").await;
+        let screen = h.screen();
+        assert!(screen.contains("Stderr (1 lines)"));
+        assert!(screen.contains("Last error: Claude CLI exited with code 3"));
+        h.key(KeyModifiers::NONE, KeyCode::Esc).await;
+        assert!(!h.screen().contains("Stderr (1 lines)"));
+    }
+
+    #[tokio::test]
+    async fn test_crash_mid_turn_closes_turn() {
+        let scenario = [
+            r#"{"type":"stream_event","event":{"type":"message_start","message":{"id":"msg_1","content":[]}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"text_delta","text":"partial answer"}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_start","index":1,"content_block":{"type":"tool_use","id":"toolu_1","name":"Read","input":{}}}}"#,
+            "sleep 0.3",
+            "stderr Error: connection reset",
+            "exit 2",
+        ]
+        .join("\n");
+        let mut h = Harness::new(&scenario);
+
+        h.submit("first").await;
+        h.submit("second").await;
+        assert!(h.screen().contains("Queued (1 pending)"));
+
+        // The queued message goes to a new process, which crashes the same
+        // way; both turns close with their tool calls no longer running
+        let screen = h
+            .wait_until("both turns closed", |screen| {
+                screen.matches("exited with code 2").count() == 2 && !screen.contains("running")
+            })
+            .await;
+        assert!(h.stdin_log().contains(r#""text":"second""#));
+        assert_eq!(screen.matches("partial answer").count(), 2);
+        assert_eq!(screen.matches("Read  interrupted").count(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_bash_output_routed_to_its_command() {
+        let mut h = Harness::new("");
+
+        // The slow command finishes last; its output must not land on the
+        // newer entry
+        h.submit("!sleep 0.3; echo slow-done").await;
+        h.submit("!echo fast-done").await;
+        let screen = h
+            .wait_until("both commands", |screen| {
+                screen.contains("slow-done") && !screen.contains("running...")
+            })
+            .await;
+
+        let slow = screen.find("$ sleep 0.3; echo slow-done").unwrap();
+        let fast = screen.find("$ echo fast-done").unwrap();
+        let slow_output = screen.rfind("slow-done").unwrap();
+        let fast_output = screen.rfind("fast-done").unwrap();
+        assert!(slow < slow_output && slow_output < fast);
+        assert!(fast < fast_output);
+    }
+
+    #[tokio::test]
+    async fn test_stale_process_does_not_end_new_turn() {
+        let mut h = Harness::new(&format!("sleep 0.5\n{}\n{}\n", reply("answer"), RESULT));
+
+        h.submit("first").await;
+        h.wait_for("answer").await;
+        h.wait_for("Ready").await;
+
+        // Changing the model drops the first process; its exit is reported
+        // while the second turn is still running
+        h.submit("/model haiku").await;
+        h.submit("second").await;
+        h.settle(Duration::from_millis(250)).await;
+        assert!(h.screen().contains("Processing..."));
+
+        h.wait_until("second answer", |screen| screen.matches("answer").count() == 2).await;
+    }
+
+    #[tokio::test]
+    async fn test_system_prompt_passed_and_shown() {
+        let mut h = Harness::with_options(&format!("{}\n{}\n", reply("prompt-ok"), RESULT), |options| {
+            options.append_system_prompt = Some("Answer in haiku".to_string());
+        });
+
+        h.submit("/system").await;
+        let screen = h.screen();
+        assert!(screen.contains("System prompt: Claude Code default"));
+        assert!(screen.contains("Appended from --append-system-prompt:"));
+        assert!(screen.contains("Answer in haiku"));
+
+        h.submit("hi").await;
+        h.wait_for("prompt-ok").await;
+        assert!(h.stdin_log().contains("--append-system-prompt Answer in haiku"));
+    }
+
+    #[tokio::test]
+    async fn test_cd_moves_bash_and_claude() {
+        let init = r#"{"type":"system","subtype":"init","session_id":"s1","tools":[]}"#;
+        let mut h = Harness::new(&format!("{}\n{}\n{}\n", init, reply("moved"), RESULT));
+        let dir = h.dir.canonicalize().unwrap();
+        std::fs::create_dir(dir.join("sub")).unwrap();
+        let sub = dir.join("sub");
+
+        h.submit("before").await;
+        h.wait_for("moved").await;
+        h.settle(Duration::from_millis(200)).await;
+        h.submit(&format!("/cd {}", dir.display())).await;
+        h.submit("/cd sub").await;
+        h.submit("/add-dir ..").await;
+
+        h.submit("!pwd > where.txt").await;
+        h.wait_until("pwd to finish", |screen| !screen.contains("running...")).await;
+        let bash_dir = std::fs::read_to_string(sub.join("where.txt")).unwrap();
+        assert_eq!(bash_dir.trim(), sub.display().to_string());
+
+        assert!(h.screen().contains("won't remember the conversation above"));
+        h.submit("hi").await;
+        h.wait_until("second reply", |screen| screen.matches("moved").count() == 2).await;
+        let log = h.stdin_log();
+        assert!(log.contains(&format!("cwd: {}", sub.display())));
+        assert!(log.contains(&format!("--add-dir {}", dir.display())));
+        // The session belongs to the old directory, so it isn't resumed
+        let spawn = log.lines().rfind(|line| line.starts_with("args: ")).unwrap();
+        assert!(!spawn.contains("--resume") && !spawn.contains("--continue"));
+    }
+
+    #[tokio::test]
+    async fn test_resume_picker_resumes_past_session() {
+        let mut h = Harness::new(&format!("{}\n{}\n", reply("picked up"), RESULT));
+        let dir = h.dir.canonicalize().unwrap();
+        // Only this test looks up transcripts, so setting this for the whole
+        // process is safe
+        std::env::set_var("CLAUDE_CONFIG_DIR", dir.join("config"));
+        let transcripts = crate::claude::project_transcripts_dir(&dir).unwrap();
+        std::fs::create_dir_all(&transcripts).unwrap();
+        std::fs::write(
+            transcripts.join("abc-123.jsonl"),
+            concat!(
+                r#"{"type":"user","message":{"role":"user","content":"Where is the config?"}}"#,
+                "\n",
+                r#"{"type":"assistant","message":{"id":"msg_1","role":"assistant","content":[{"type":"text","text":"In config.rs."}]}}"#,
+                "\n",
+            ),
+        )
+        .unwrap();
+
+        h.submit(&format!("/cd {}", dir.display())).await;
+        h.submit("/resume").await;
+        let screen = h.wait_for("Resume session").await;
+        assert!(screen.contains("2 msgs  Where is the config?"));
+
+        h.key(KeyModifiers::NONE, KeyCode::Enter).await;
+        assert!(h.screen().contains("In config.rs."));
+
+        h.submit("and the tests?").await;
+        h.wait_for("picked up").await;
+        assert!(h.stdin_log().contains("--resume abc-123"));
+    }
+
+    #[tokio::test]
+    async fn test_budget_warns_stops_and_releases_held_messages() {
+        // Each turn costs $0.01
+        let scenario = format!(
+            "{}\n{}\n---\n{}\n{}\n---\n{}\n{}\n---\n{}\n{}\n",
+            reply("one"),
+            RESULT,
+            reply("two"),
+            RESULT,
+            reply("three"),
+            RESULT,
+            reply("four"),
+            RESULT
+        );
+        let mut h = Harness::new(&scenario);
+
+        h.submit("/budget cost NaN").await;
+        assert!(h.screen().contains("Usage: /budget"));
+        h.submit("/budget cost 0.012").await;
+        h.submit("first").await;
+        h.wait_for("Warning: session cost budget 83% used").await;
+
+        h.submit("second").await;
+        let screen = h.wait_for("Stopped: session cost budget reached").await;
+        assert!(screen.contains("Budget reached"));
+
+        h.submit("third").await;
+        h.submit("fourth").await;
+        h.settle(Duration::from_millis(200)).await;
+        assert!(!h.stdin_log().contains(r#""text":"third""#));
+
+        // Held messages go out in the order they were typed
+        h.submit("/budget cost 1").await;
+        h.wait_for("four").await;
+        assert!(!h.screen().contains("Budget reached"));
+        let log = h.stdin_log();
+        let third = log.find(r#""text":"third""#).unwrap();
+        let fourth = log.find(r#""text":"fourth""#).unwrap();
+        assert!(third < fourth);
+    }
+
+    #[tokio::test]
+    async fn test_model_picker_and_reported_model() {
+        let init = r#"{"type":"system","subtype":"init","session_id":"s1","model":"claude-opus-4-1-20250805","tools":[]}"#;
+        let mut h = Harness::new(&format!("{}\n{}\n{}\n", init, reply("picked"), RESULT));
+
+        h.submit("/model sonet").await;
+        assert!(h.screen().contains("did you mean sonnet?"));
+
+        h.submit("/model").await;
+        let screen = h.screen();
+        assert!(screen.contains("Claude Sonnet 4.5"));
+        assert!(screen.contains("* sonnet"));
+        h.key(KeyModifiers::NONE, KeyCode::Down).await;
+        h.key(KeyModifiers::NONE, KeyCode::Enter).await;
+        assert!(h.screen().contains("Model set to: opus"));
+
+        h.submit("hi").await;
+        let screen = h.wait_for("picked").await;
+        assert!(screen.contains(" claude-opus-4-1-20250805 "));
+        assert!(h.stdin_log().contains("--model opus"));
+    }
+
+    #[tokio::test]
+    async fn test_typing_does_not_answer_permission_prompt() {
+        let mut h = Harness::new("");
+        let dir = h.dir.canonicalize().unwrap();
+        h.submit(&format!("/cd {}", dir.display())).await;
+
+        // A prompt pops up while a message is being typed
+        for c in "say".chars() {
+            h.key(KeyModifiers::NONE, KeyCode::Char(c)).await;
+        }
+        let (responder, mut decision) = tokio::sync::oneshot::channel();
+        let request = PermissionRequest {
+            tool_name: "Bash".to_string(),
+            input: serde_json::json!({"command": "rm -rf build"}),
+            responder,
+        };
+        h.app.handle_app_message(AppMessage::PermissionRequest(request)).await.unwrap();
+        assert!(h.screen().contains("Permission required"));
+        for c in " yay".chars() {
+            h.key(KeyModifiers::NONE, KeyCode::Char(c)).await;
+        }
+        h.key(KeyModifiers::NONE, KeyCode::Enter).await;
+        assert!(decision.try_recv().is_err());
+
+        // Once it has been up a moment, Enter still doesn't allow and `a`
+        // asks before saving a rule
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        h.key(KeyModifiers::NONE, KeyCode::Enter).await;
+        h.key(KeyModifiers::NONE, KeyCode::Char('a')).await;
+        assert!(h.screen().contains("Always allow Bash(rm -rf build) in this project?"));
+        assert!(decision.try_recv().is_err());
+        assert!(!dir.join(".claude-terminal/permissions.json").exists());
+
+        h.key(KeyModifiers::NONE, KeyCode::Char('a')).await;
+        assert_eq!(decision.try_recv().unwrap(), PermissionDecision::AlwaysAllow);
+        let rules = std::fs::read_to_string(dir.join(".claude-terminal/permissions.json")).unwrap();
+        assert!(rules.contains("Bash(rm -rf build)"));
+        assert!(h.screen().contains("say"));
+    }
+
+    #[tokio::test]
+    async fn test_subagent_messages_kept_apart() {
+        let scenario = [
+            r#"{"type":"assistant","message":{"id":"msg_1","content":[{"type":"tool_use","id":"toolu_task","name":"Task","input":{"description":"look"}}]}}"#,
+            r#"{"type":"assistant","message":{"id":"msg_s1","content":[{"type":"text","text":"I'll check."}]},"parent_tool_use_id":"toolu_task"}"#,
+            r#"{"type":"assistant","message":{"id":"msg_s2","content":[{"type":"text","text":"Found it."}]},"parent_tool_use_id":"toolu_task"}"#,
+            RESULT,
+        ]
+        .join("\n");
+        let mut h = Harness::new(&scenario);
+
+        h.submit("hi").await;
+        h.wait_for("Found it.").await;
+        h.key(KeyModifiers::CONTROL, KeyCode::Char('o')).await;
+        let screen = h.screen();
+        assert!(screen.contains("| I'll check."));
+        assert!(screen.contains("| Found it."));
+        assert!(!screen.contains("I'll check.Found it."));
+    }
+
+    #[tokio::test]
+    async fn test_shift_tab_mode_applies_to_next_spawn() {
+        let mut h = Harness::new(&format!("{}\n{}\n", reply("done"), RESULT));
+
+        h.submit("one").await;
+        h.wait_for("done").await;
+        assert!(h.stdin_log().contains("--permission-mode default"));
+
+        h.key(KeyModifiers::SHIFT, KeyCode::BackTab).await;
+        assert!(h.screen().contains("Permission mode: plan (applies to next message)"));
+        h.submit("two").await;
+        h.wait_until("second reply", |screen| screen.matches("done").count() == 2).await;
+        let log = h.stdin_log();
+        let spawns: Vec<&str> = log.lines().filter(|line| line.starts_with("args: ")).collect();
+        assert_eq!(spawns.len(), 2);
+        assert!(spawns[1].contains("--permission-mode plan"));
+    }
+
+    #[tokio::test]
+    async fn test_context_gauge_offers_compact() {
+        let scenario = [
+            r#"{"type":"assistant","message":{"id":"msg_1","content":[{"type":"text","text":"big answer"}],"usage":{"input_tokens":1000,"cache_read_input_tokens":169000,"output_tokens":50}}}"#,
+            RESULT,
+            "---",
+            r#"{"type":"system","subtype":"compact_boundary","session_id":"s1","compact_metadata":{"trigger":"manual","pre_tokens":170000}}"#,
+            RESULT,
+        ]
+        .join("\n");
+        let mut h = Harness::new(&scenario);
+
+        h.submit("hi").await;
+        let screen = h.wait_for("Context almost full").await;
+        assert!(screen.contains("using 85% of the context window"));
+        assert!(screen.contains("Ctx: 85%"));
+
+        // Typing that was under way when the offer appeared doesn't answer
+        // it, and Enter never does
+        h.key(KeyModifiers::NONE, KeyCode::Char('n')).await;
+        assert!(h.screen().contains("Context almost full"));
+        tokio::time::sleep(Duration::from_millis(600)).await;
+        h.key(KeyModifiers::NONE, KeyCode::Enter).await;
+        assert!(h.screen().contains("Context almost full"));
+        h.key(KeyModifiers::NONE, KeyCode::Char('y')).await;
+        let screen = h.wait_for("Conversation compacted on request (was 170.0K tokens)").await;
+        assert!(!screen.contains("Context almost full"));
+        assert!(!screen.contains("Ctx:"));
+        assert!(h.stdin_log().contains(r#""text":"/compact""#));
+    }
+
+    #[tokio::test]
+    async fn test_custom_command_listed_completed_and_forwarded() {
+        let mut h = Harness::new(&format!("{}\n{}\n", reply("deployed"), RESULT));
+        h.submit("/nope").await;
+        assert!(h.screen().contains("Unknown command: /nope"));
+
+        let commands = h.dir.join(".claude").join("commands");
+        std::fs::create_dir_all(&commands).unwrap();
+        let deploy = "---\ndescription: Ship to an environment\nargument-hint: <env>\n---\nDeploy.";
+        std::fs::write(commands.join("deploy.md"), deploy).unwrap();
+        h.submit(&format!("/cd {}", h.dir.display())).await;
+
+        h.submit("/help").await;
+        let screen = h.screen();
+        assert!(screen.contains("/deploy <env>  Ship to an environment (project)"));
+        assert!(screen.contains("/init"));
+
+        for c in "/dep".chars() {
+            h.key(KeyModifiers::NONE, KeyCode::Char(c)).await;
+        }
+        assert!(h.screen().contains("Ship to an environment"));
+        h.key(KeyModifiers::NONE, KeyCode::Tab).await;
+        h.submit("prod").await;
+        h.wait_for("deployed").await;
+        assert!(h.stdin_log().contains(r#""text":"/deploy prod""#));
+
+    }
+
+    #[tokio::test]
+    async fn test_finished_entries_saved_during_session() {
+        let mut h = Harness::new(&format!("{}\n{}\n", reply("first answer"), RESULT));
+        let root = h.dir.join("state");
+        h.app.set_state_store(StateStore::create(&root).unwrap());
+
+        h.submit("hello").await;
+        h.wait_until("the turn to end", |screen| {
+            screen.contains("first answer") && screen.contains("Ready")
+        })
+        .await;
+
+        let store = std::fs::read_dir(&root).unwrap().next().unwrap().unwrap().path();
+        let entries = std::fs::read_to_string(store.join("entries.jsonl")).unwrap();
+        assert_eq!(entries.lines().count(), 2);
+        assert!(entries.contains("hello") && entries.contains("first answer"));
+    }
+
+    #[tokio::test]
+    async fn test_crashed_session_restored() {
+        let mut h = Harness::new(&format!("{}\n{}\n", reply("welcome back"), RESULT));
+        let root = h.dir.join("state");
+        let mut crashed = StateStore::create(&root).unwrap();
+        let text = |role, text: &str| {
+            ConversationEntry::new(role, ConversationContent::Text(text.to_string()))
+        };
+        let bash = ConversationContent::BashCommand {
+            id: RequestId(7),
+            command: "echo hi".to_string(),
+            output: "hi-from-bash\n".to_string(),
+            exit_code: Some(0),
+        };
+        crashed
+            .append(&[
+                text(Role::User, "before the crash"),
+                text(Role::Assistant, "an answer"),
+                ConversationEntry::new(Role::Bash, bash),
+            ])
+            .unwrap();
+        crashed
+            .save_snapshot(Snapshot {
+                // Not a running process
+                pid: i32::MAX as u32,
+                working_dir: h.dir.clone(),
+                claude_session_id: Some("session-42".to_string()),
+                input: "half a thought".to_string(),
+                cursor_position: 14,
+                message_queue: vec!["queued question".to_string()],
+                input_history: vec!["before the crash".to_string()],
+            })
+            .unwrap();
+
+        h.app.offer_restore(SavedState::find(&root, &h.dir).unwrap());
+        let screen = h.screen();
+        assert!(screen.contains("didn't exit cleanly"));
+        assert!(screen.contains("3 conversation entries, 1 queued messages, an unsent draft"));
+
+        h.key(KeyModifiers::NONE, KeyCode::Char('y')).await;
+        let screen = h.wait_for("welcome back").await;
+        assert!(screen.contains("before the crash"));
+        assert!(screen.contains("hi-from-bash"));
+        assert!(screen.contains("half a thought"));
+        let log = h.stdin_log();
+        assert!(log.contains("--resume session-42"));
+        assert!(log.contains("queued question"));
+        assert!(SavedState::find(&root, &h.dir).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_export_writes_conversation() {
+        let mut h = Harness::new(&format!("{}\n{}\n", reply("exported answer"), RESULT));
+        h.submit("/export").await;
+        assert!(h.screen().contains("Nothing to export yet"));
+
+        h.submit("!echo from-bash").await;
+        h.wait_until("echo to finish", |screen| !screen.contains("running...")).await;
+        h.submit("question").await;
+        h.wait_until("the turn to end", |screen| {
+            screen.contains("exported answer") && screen.contains("Ready")
+        })
+        .await;
+
+        let path = h.dir.join("notes.json");
+        h.submit(&format!("/export {}", path.display())).await;
+        assert!(h.screen().contains("Exported 3 entries"));
+        let json: serde_json::Value =
+            serde_json::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries[0]["command"], "echo from-bash");
+        assert_eq!(entries[0]["exit_code"], 0);
+        assert_eq!(entries[1]["text"], "question");
+        assert_eq!(entries[2]["role"], "assistant");
+
+        let path = h.dir.join("notes.txt");
+        std::fs::write(&path, "my notes").unwrap();
+        h.submit(&format!("/export md {}", path.display())).await;
+        assert!(h.screen().contains("Export failed: add --force to overwrite"));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "my notes");
+        h.submit(&format!("/export md {} --force", path.display())).await;
+        let markdown = std::fs::read_to_string(&path).unwrap();
+        assert!(markdown.contains("## Claude\n\nexported answer"));
+    }
+
+    #[tokio::test]
+    async fn test_mcp_servers_listed_and_tools_named() {
+        let scenario = [
+            r#"{"type":"system","subtype":"init","session_id":"s1","model":"sonnet","tools":["Read","mcp__github__create_issue","mcp__github__list_prs"],"mcp_servers":[{"name":"github","status":"connected"},{"name":"db","status":"failed"}]}"#.to_string(),
+            r#"{"type":"assistant","message":{"id":"msg_1","content":[{"type":"tool_use","id":"toolu_1","name":"mcp__github__create_issue","input":{"title":"Bug"}}]}}"#.to_string(),
+            r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"issue 12"}]}}"#.to_string(),
+            reply("Filed it"),
+            RESULT.to_string(),
+        ]
+        .join("\n");
+        let mut h = Harness::with_options(&scenario, |options| {
+            options.mcp_configs = vec!["servers.json".to_string()];
+            options.strict_mcp_config = true;
+        });
+
+        h.submit("file a bug").await;
+        let screen = h.wait_for("Filed it").await;
+        assert!(screen.contains("[Tool] github create_issue  done"));
+        let log = h.stdin_log();
+        assert!(log.contains("--mcp-config servers.json "));
+        assert!(log.contains("--strict-mcp-config"));
+
+        h.submit("/mcp").await;
+        let screen = h.screen();
+        assert!(screen.contains("github (connected) - 2 tool(s): create_issue, list_prs"));
+        assert!(screen.contains("db (failed) - no tools"));
+        assert!(screen.contains("Config: servers.json"));
+    }
+}
@@ -1,6 +1,7 @@
 //! Bash command executor
 
 use anyhow::Result;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use tokio::{
     io::{AsyncBufReadExt, BufReader},
@@ -8,33 +9,44 @@ use tokio::{
     sync::mpsc,
 };
 
-use crate::app::AppMessage;
+use crate::app::{AppMessage, RequestId};
 
 /// Executes bash commands and sends output to the app
 pub struct BashExecutor {
     message_tx: mpsc::Sender<AppMessage>,
+    /// Directory commands run in
+    working_dir: PathBuf,
 }
 
 impl BashExecutor {
-    pub fn new(message_tx: mpsc::Sender<AppMessage>) -> Self {
-        Self { message_tx }
+    pub fn new(message_tx: mpsc::Sender<AppMessage>, working_dir: PathBuf) -> Self {
+        Self {
+            message_tx,
+            working_dir,
+        }
+    }
+
+    /// Run later commands in `dir`
+    pub fn set_working_dir(&mut self, dir: &Path) {
+        self.working_dir = dir.to_path_buf();
     }
 
-    /// Execute a bash command
-    pub async fn execute(&self, command: &str) -> Result<()> {
+    /// Execute a bash command; its output is reported under `id`
+    pub async fn execute(&self, id: RequestId, command: &str) -> Result<()> {
         let tx = self.message_tx.clone();
         let command = command.to_string();
+        let working_dir = self.working_dir.clone();
 
         tokio::spawn(async move {
-            let result = execute_command(&command).await;
+            let result = execute_command(&command, &working_dir).await;
             match result {
                 Ok((output, exit_code)) => {
-                    let _ = tx.send(AppMessage::BashOutput(output)).await;
-                    let _ = tx.send(AppMessage::BashFinished(exit_code)).await;
+                    let _ = tx.send(AppMessage::BashOutput(id, output)).await;
+                    let _ = tx.send(AppMessage::BashFinished(id, exit_code)).await;
                 }
                 Err(e) => {
-                    let _ = tx.send(AppMessage::BashOutput(format!("Error: {}", e))).await;
-                    let _ = tx.send(AppMessage::BashFinished(1)).await;
+                    let _ = tx.send(AppMessage::BashOutput(id, format!("Error: {}", e))).await;
+                    let _ = tx.send(AppMessage::BashFinished(id, 1)).await;
                 }
             }
         });
@@ -43,16 +55,18 @@ impl BashExecutor {
     }
 }
 
-async fn execute_command(command: &str) -> Result<(String, i32)> {
+async fn execute_command(command: &str, working_dir: &Path) -> Result<(String, i32)> {
     let mut child = if cfg!(target_os = "windows") {
         Command::new("cmd")
             .args(["/C", command])
+            .current_dir(working_dir)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?
     } else {
         Command::new("sh")
             .args(["-c", command])
+            .current_dir(working_dir)
             .stdout(Stdio::piped())
             .stderr(Stdio::piped())
             .spawn()?
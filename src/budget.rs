@@ -0,0 +1,331 @@
+//! Token and cost budgets for a session or a project
+
+use anyhow::{bail, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::ui::{format_cost, format_tokens};
+
+/// Project spending file, relative to the project directory
+pub const PROJECT_USAGE_FILE: &str = ".claude-terminal/usage.json";
+
+/// Fraction of a limit at which to warn when `warn_at` isn't set
+pub const DEFAULT_WARN_AT: f64 = 0.8;
+
+/// Limits on spending. Unset limits don't apply.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Budget {
+    /// Tokens processed, counting cache reads and writes
+    pub max_tokens: Option<u64>,
+    /// Cost in USD, from result events
+    pub max_cost_usd: Option<f64>,
+    /// Fraction of a limit (0-1) at which to warn
+    pub warn_at: Option<f64>,
+    /// Passed to the CLI as `--max-turns`
+    pub max_turns: Option<u32>,
+}
+
+impl Budget {
+    /// Take the limits `other` sets, keeping ours for the rest
+    pub fn merge(&mut self, other: Budget) {
+        self.max_tokens = other.max_tokens.or(self.max_tokens);
+        self.max_cost_usd = other.max_cost_usd.or(self.max_cost_usd);
+        self.warn_at = other.warn_at.or(self.warn_at);
+        self.max_turns = other.max_turns.or(self.max_turns);
+    }
+
+    /// Reject limits from a config file that can't apply
+    pub fn validate(&self) -> Result<()> {
+        if let Some(usd) = self.max_cost_usd {
+            if !valid_cost(usd) {
+                bail!("max_cost_usd must be an amount in USD of 0 or more, not {}", usd);
+            }
+        }
+        if let Some(fraction) = self.warn_at {
+            if !valid_warn_at(fraction) {
+                bail!(
+                    "warn_at is a fraction of the limit from 0 to 1 (0.8 warns at 80%), not {}",
+                    fraction
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Limits that `spend` is close to or over. `scope` ("session" or
+    /// "project") names the budget in messages.
+    pub fn check(&self, scope: &str, spend: Spend) -> Vec<BudgetAlert> {
+        let warn_at = self.warn_at.unwrap_or(DEFAULT_WARN_AT);
+        let limits = [
+            self.max_tokens.map(|max| {
+                let tokens = (format_tokens(spend.tokens), format_tokens(max));
+                let used = format!("{} of {} tokens", tokens.0, tokens.1);
+                ("tokens", spend.tokens as f64, max as f64, used)
+            }),
+            self.max_cost_usd.map(|max| {
+                let used = format!("{} of {}", format_cost(spend.cost_usd), format_cost(max));
+                ("cost", spend.cost_usd, max, used)
+            }),
+        ];
+
+        limits
+            .into_iter()
+            .flatten()
+            .filter(|(_, used, max, _)| *used >= max * warn_at)
+            .map(|(kind, used, max, text)| {
+                let exceeded = used >= max;
+                let limit = format!("{} {}", scope, kind);
+                let message = if exceeded {
+                    format!("{} budget reached: {}", limit, text)
+                } else {
+                    let percent = if max > 0.0 { used / max * 100.0 } else { 100.0 };
+                    format!("{} budget {:.0}% used: {}", limit, percent, text)
+                };
+                BudgetAlert {
+                    limit,
+                    exceeded,
+                    message,
+                }
+            })
+            .collect()
+    }
+
+    /// One-line description of the limits
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(max) = self.max_tokens {
+            parts.push(format!("{} tokens", format_tokens(max)));
+        }
+        if let Some(max) = self.max_cost_usd {
+            parts.push(format_cost(max));
+        }
+        if parts.is_empty() {
+            return "no limit".to_string();
+        }
+        let warn_at = self.warn_at.unwrap_or(DEFAULT_WARN_AT);
+        format!("{} (warn at {:.0}%)", parts.join(", "), warn_at * 100.0)
+    }
+}
+
+/// A budget limit that has been neared or passed
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetAlert {
+    /// Which limit, e.g. "session cost"
+    pub limit: String,
+    /// Over the hard limit rather than past the warning threshold
+    pub exceeded: bool,
+    pub message: String,
+}
+
+/// Tokens and cost spent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Spend {
+    pub tokens: u64,
+    pub cost_usd: f64,
+}
+
+impl Spend {
+    pub fn add(self, other: Spend) -> Spend {
+        Spend {
+            tokens: self.tokens + other.tokens,
+            cost_usd: self.cost_usd + other.cost_usd,
+        }
+    }
+
+    pub fn sub(self, other: Spend) -> Spend {
+        Spend {
+            tokens: self.tokens.saturating_sub(other.tokens),
+            cost_usd: (self.cost_usd - other.cost_usd).max(0.0),
+        }
+    }
+
+    /// Spending recorded for a project; nothing if the file is missing or bad
+    pub fn load(project_dir: &Path) -> Spend {
+        std::fs::read_to_string(project_dir.join(PROJECT_USAGE_FILE))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    /// Record a project's spending
+    pub fn save(&self, project_dir: &Path) -> Result<()> {
+        let path = project_dir.join(PROJECT_USAGE_FILE);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(&path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Session and project budgets, with what has been spent against them
+#[derive(Debug, Clone, Default)]
+pub struct Budgets {
+    pub session: Budget,
+    pub project: Budget,
+    /// Project spending from earlier sessions
+    pub project_base: Spend,
+    /// Session spending when this project was entered
+    pub project_start: Spend,
+}
+
+impl Budgets {
+    /// Project spending including this session's share
+    pub fn project_spend(&self, session: Spend) -> Spend {
+        self.project_base.add(session.sub(self.project_start))
+    }
+
+    /// Alerts for both budgets
+    pub fn check(&self, session: Spend) -> Vec<BudgetAlert> {
+        let mut alerts = self.session.check("session", session);
+        alerts.extend(self.project.check("project", self.project_spend(session)));
+        alerts
+    }
+
+    /// `--max-turns` for the CLI; the tighter of the two budgets
+    pub fn max_turns(&self) -> Option<u32> {
+        match (self.session.max_turns, self.project.max_turns) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Parse a token count such as `50000`, `200k` or `1.5m`
+pub fn parse_token_count(text: &str) -> Option<u64> {
+    let text = text.trim().to_ascii_lowercase();
+    let (number, scale) = match text.chars().last()? {
+        'k' => (&text[..text.len() - 1], 1_000.0),
+        'm' => (&text[..text.len() - 1], 1_000_000.0),
+        _ => (text.as_str(), 1.0),
+    };
+    let value: f64 = number.parse().ok()?;
+    (value >= 0.0).then(|| (value * scale).round() as u64)
+}
+
+/// Parse an amount in USD such as `5`, `0.50` or `$2`
+pub fn parse_cost(text: &str) -> Option<f64> {
+    let usd: f64 = text.trim().trim_start_matches('$').parse().ok()?;
+    valid_cost(usd).then_some(usd)
+}
+
+/// Parse a warning threshold given in percent, such as `80` or `75%`, as
+/// the fraction `warn_at` holds
+pub fn parse_warn_percent(text: &str) -> Option<f64> {
+    let percent: f64 = text.trim().trim_end_matches('%').parse().ok()?;
+    let fraction = percent / 100.0;
+    valid_warn_at(fraction).then_some(fraction)
+}
+
+fn valid_cost(usd: f64) -> bool {
+    usd.is_finite() && usd >= 0.0
+}
+
+fn valid_warn_at(fraction: f64) -> bool {
+    (0.0..=1.0).contains(&fraction)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check_warns_then_stops() {
+        let budget = Budget {
+            max_cost_usd: Some(1.0),
+            ..Default::default()
+        };
+        let spend = |cost_usd| Spend {
+            tokens: 0,
+            cost_usd,
+        };
+
+        assert!(budget.check("session", spend(0.5)).is_empty());
+
+        let alerts = budget.check("session", spend(0.85));
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].limit, "session cost");
+        assert!(!alerts[0].exceeded);
+        assert_eq!(alerts[0].message, "session cost budget 85% used: $0.85 of $1.00");
+
+        let alerts = budget.check("session", spend(1.0));
+        assert!(alerts[0].exceeded);
+        assert_eq!(alerts[0].message, "session cost budget reached: $1.00 of $1.00");
+    }
+
+    #[test]
+    fn test_project_spend_counts_only_this_project() {
+        let budgets = Budgets {
+            project: Budget {
+                max_tokens: Some(1_000),
+                warn_at: Some(0.5),
+                ..Default::default()
+            },
+            project_base: Spend {
+                tokens: 400,
+                cost_usd: 0.4,
+            },
+            project_start: Spend {
+                tokens: 300,
+                cost_usd: 0.3,
+            },
+            ..Default::default()
+        };
+        let session = Spend {
+            tokens: 450,
+            cost_usd: 0.5,
+        };
+
+        let project = budgets.project_spend(session);
+        assert_eq!(project.tokens, 550);
+        assert!((project.cost_usd - 0.6).abs() < 1e-9);
+        let alerts = budgets.check(session);
+        assert_eq!(alerts.len(), 1);
+        assert_eq!(alerts[0].limit, "project tokens");
+    }
+
+    #[test]
+    fn test_parse_token_count() {
+        assert_eq!(parse_token_count("50000"), Some(50_000));
+        assert_eq!(parse_token_count("200k"), Some(200_000));
+        assert_eq!(parse_token_count("1.5M"), Some(1_500_000));
+        assert_eq!(parse_token_count("lots"), None);
+        assert_eq!(parse_token_count(""), None);
+    }
+
+    #[test]
+    fn test_parse_cost() {
+        assert_eq!(parse_cost("5"), Some(5.0));
+        assert_eq!(parse_cost("$0.50"), Some(0.5));
+        assert_eq!(parse_cost("-1"), None);
+        assert_eq!(parse_cost("NaN"), None);
+        assert_eq!(parse_cost("inf"), None);
+        assert_eq!(parse_cost("cheap"), None);
+    }
+
+    #[test]
+    fn test_validate_names_units() {
+        assert_eq!(parse_warn_percent("75%"), Some(0.75));
+        assert_eq!(parse_warn_percent("120"), None);
+
+        let budget = Budget {
+            max_cost_usd: Some(2.0),
+            warn_at: Some(0.8),
+            ..Default::default()
+        };
+        assert!(budget.validate().is_ok());
+        let budget = Budget {
+            warn_at: Some(80.0),
+            ..Default::default()
+        };
+        let error = budget.validate().unwrap_err().to_string();
+        assert!(error.contains("fraction of the limit from 0 to 1"), "{}", error);
+        let budget = Budget {
+            max_cost_usd: Some(-1.0),
+            ..Default::default()
+        };
+        assert!(budget.validate().is_err());
+    }
+}
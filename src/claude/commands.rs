@@ -0,0 +1,227 @@
+//! Slash commands run by the Claude CLI: its own prompt commands and custom
+//! commands from `.claude/commands`
+
+use std::path::Path;
+
+/// Custom commands directory, relative to the project directory
+pub const PROJECT_COMMANDS_DIR: &str = ".claude/commands";
+
+/// Built-in CLI commands that work when sent as a message
+const CLI_COMMANDS: &[(&str, &str)] = &[
+    ("compact", "Summarize the conversation to free context"),
+    ("init", "Create a CLAUDE.md with notes about the codebase"),
+    ("review", "Review a pull request"),
+    ("pr-comments", "Get comments from a GitHub pull request"),
+    ("security-review", "Review pending changes for security issues"),
+];
+
+/// Where a command comes from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum CommandSource {
+    /// Built into the CLI
+    Cli,
+    /// `.claude/commands` in the project; the subdirectory, if any
+    Project(Option<String>),
+    /// `~/.claude/commands`; the subdirectory, if any
+    User(Option<String>),
+}
+
+/// A slash command the CLI will run when sent as a message
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClaudeCommand {
+    pub name: String,
+    pub description: String,
+    /// Shown after the name, e.g. `<issue-number>`
+    pub argument_hint: Option<String>,
+    pub source: CommandSource,
+}
+
+impl ClaudeCommand {
+    /// Where the command is defined, e.g. `project:frontend`
+    pub fn origin(&self) -> String {
+        let (scope, subdir) = match &self.source {
+            CommandSource::Cli => return "claude".to_string(),
+            CommandSource::Project(subdir) => ("project", subdir),
+            CommandSource::User(subdir) => ("user", subdir),
+        };
+        match subdir {
+            Some(subdir) => format!("{}:{}", scope, subdir),
+            None => scope.to_string(),
+        }
+    }
+}
+
+/// The CLI's commands plus custom commands from the project and
+/// `~/.claude/commands`. The first definition of a name wins, so project
+/// commands shadow user commands.
+pub fn load_claude_commands(project_dir: &Path) -> Vec<ClaudeCommand> {
+    let mut commands: Vec<ClaudeCommand> = CLI_COMMANDS
+        .iter()
+        .map(|(name, description)| ClaudeCommand {
+            name: name.to_string(),
+            description: description.to_string(),
+            argument_hint: None,
+            source: CommandSource::Cli,
+        })
+        .collect();
+    let project_commands = project_dir.join(PROJECT_COMMANDS_DIR);
+    scan_commands(&project_commands, None, &CommandSource::Project, &mut commands);
+    if let Some(home) = dirs::home_dir() {
+        let user_commands = home.join(".claude").join("commands");
+        scan_commands(&user_commands, None, &CommandSource::User, &mut commands);
+    }
+    commands
+}
+
+/// Find a command by name
+pub fn find_claude_command<'a>(
+    commands: &'a [ClaudeCommand],
+    name: &str,
+) -> Option<&'a ClaudeCommand> {
+    commands.iter().find(|command| command.name == name)
+}
+
+/// Add the `.md` commands under `dir`; subdirectories only organize them
+fn scan_commands(
+    dir: &Path,
+    subdir: Option<&str>,
+    source: &dyn Fn(Option<String>) -> CommandSource,
+    commands: &mut Vec<ClaudeCommand>,
+) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<_> = entries.filter_map(|entry| entry.ok().map(|e| e.path())).collect();
+    paths.sort();
+
+    for path in paths {
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        if path.is_dir() {
+            let nested = match subdir {
+                Some(subdir) => format!("{}:{}", subdir, file_name),
+                None => file_name.to_string(),
+            };
+            scan_commands(&path, Some(&nested), source, commands);
+            continue;
+        }
+        let Some(name) = file_name.strip_suffix(".md") else {
+            continue;
+        };
+        if find_claude_command(commands, name).is_some() {
+            continue;
+        }
+        let content = std::fs::read_to_string(&path).unwrap_or_default();
+        let frontmatter = parse_frontmatter(&content);
+        commands.push(ClaudeCommand {
+            name: name.to_string(),
+            description: frontmatter.description.unwrap_or_default(),
+            argument_hint: frontmatter.argument_hint,
+            source: source(subdir.map(str::to_string)),
+        });
+    }
+}
+
+/// Fields we use from a command file's frontmatter
+#[derive(Debug, Default, PartialEq)]
+pub struct Frontmatter {
+    pub description: Option<String>,
+    pub argument_hint: Option<String>,
+}
+
+/// Read `description` and `argument-hint` from a command file. Without a
+/// description, the first line of the prompt is used.
+pub fn parse_frontmatter(content: &str) -> Frontmatter {
+    let mut frontmatter = Frontmatter::default();
+    let mut body = content;
+
+    let opened = content.strip_prefix("---\n").or_else(|| content.strip_prefix("---\r\n"));
+    if let Some(rest) = opened {
+        if let Some(end) = rest.find("\n---") {
+            for line in rest[..end].lines() {
+                let Some((key, value)) = line.split_once(':') else {
+                    continue;
+                };
+                let value = value.trim().trim_matches(|c| c == '"' || c == '\'').to_string();
+                if value.is_empty() {
+                    continue;
+                }
+                match key.trim() {
+                    "description" => frontmatter.description = Some(value),
+                    "argument-hint" => frontmatter.argument_hint = Some(value),
+                    _ => {}
+                }
+            }
+            body = rest[end + 4..].trim_start_matches('-');
+        }
+    }
+
+    if frontmatter.description.is_none() {
+        frontmatter.description = body
+            .lines()
+            .map(|line| line.trim().trim_start_matches('#').trim())
+            .find(|line| !line.is_empty())
+            .map(|line| truncate(line, 80));
+    }
+    frontmatter
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(max_chars).collect();
+    format!("{}...", truncated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_frontmatter() {
+        let content = "---\ndescription: \"Fix a GitHub issue\"\nargument-hint: <number>\n\
+                       allowed-tools: Bash\n---\n\nFix issue $ARGUMENTS.\n";
+        assert_eq!(
+            parse_frontmatter(content),
+            Frontmatter {
+                description: Some("Fix a GitHub issue".to_string()),
+                argument_hint: Some("<number>".to_string()),
+            }
+        );
+
+        let content = "# Explain the code\n\nWalk through $ARGUMENTS step by step.";
+        let description = parse_frontmatter(content).description;
+        assert_eq!(description.as_deref(), Some("Explain the code"));
+
+        let content = "---\nargument-hint: <file>\n---\nSummarize the file.";
+        let description = parse_frontmatter(content).description;
+        assert_eq!(description.as_deref(), Some("Summarize the file."));
+    }
+
+    #[test]
+    fn test_load_project_commands() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let commands_dir = dir.join(PROJECT_COMMANDS_DIR);
+        std::fs::create_dir_all(commands_dir.join("frontend")).unwrap();
+        let deploy = "---\ndescription: Ship it\n---\nDeploy.";
+        std::fs::write(commands_dir.join("deploy.md"), deploy).unwrap();
+        let component = commands_dir.join("frontend").join("component.md");
+        std::fs::write(component, "Make a component").unwrap();
+        std::fs::write(commands_dir.join("notes.txt"), "not a command").unwrap();
+        // A custom command can't replace a CLI command
+        std::fs::write(commands_dir.join("init.md"), "Something else").unwrap();
+
+        let commands = load_claude_commands(dir);
+
+        let deploy = find_claude_command(&commands, "deploy").unwrap();
+        assert_eq!(deploy.description, "Ship it");
+        assert_eq!(deploy.origin(), "project");
+        let component = find_claude_command(&commands, "component").unwrap();
+        assert_eq!(component.origin(), "project:frontend");
+        assert_eq!(find_claude_command(&commands, "init").unwrap().source, CommandSource::Cli);
+        assert!(find_claude_command(&commands, "notes").is_none());
+    }
+}
@@ -0,0 +1,50 @@
+//! Helpers for MCP tools as reported by the Claude CLI
+
+/// Prefix of tools provided by MCP servers: `mcp__<server>__<tool>`
+const MCP_TOOL_PREFIX: &str = "mcp__";
+
+/// Split an MCP tool name into its server and tool, or `None` for
+/// built-in tools
+pub fn split_mcp_tool_name(name: &str) -> Option<(&str, &str)> {
+    name.strip_prefix(MCP_TOOL_PREFIX)?
+        .split_once("__")
+        .filter(|(server, tool)| !server.is_empty() && !tool.is_empty())
+}
+
+/// Tools exposed by one MCP server, without the `mcp__<server>__` prefix
+pub fn mcp_server_tools<'a>(tools: &'a [String], server: &str) -> Vec<&'a str> {
+    tools
+        .iter()
+        .filter_map(|tool| split_mcp_tool_name(tool))
+        .filter(|(tool_server, _)| *tool_server == server)
+        .map(|(_, tool)| tool)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_split_mcp_tool_name() {
+        assert_eq!(
+            split_mcp_tool_name("mcp__github__create_issue"),
+            Some(("github", "create_issue"))
+        );
+        assert_eq!(split_mcp_tool_name("mcp__my_server__do__it"), Some(("my_server", "do__it")));
+        assert_eq!(split_mcp_tool_name("Bash"), None);
+        assert_eq!(split_mcp_tool_name("mcp__github"), None);
+    }
+
+    #[test]
+    fn test_mcp_server_tools() {
+        let tools = vec![
+            "Read".to_string(),
+            "mcp__github__create_issue".to_string(),
+            "mcp__linear__list".to_string(),
+            "mcp__github__search".to_string(),
+        ];
+        assert_eq!(mcp_server_tools(&tools, "github"), vec!["create_issue", "search"]);
+        assert!(mcp_server_tools(&tools, "slack").is_empty());
+    }
+}
@@ -1,12 +1,28 @@
 //! Claude Code CLI integration
 
+mod commands;
+mod mcp;
+mod models;
+mod options;
 mod parser;
+mod partial_json;
+mod preflight;
 mod process;
+mod replay;
+mod transcript;
 mod types;
 
 #[cfg(test)]
 mod parser_tests;
 
+pub use commands::*;
+pub use mcp::*;
+pub use models::*;
+pub use options::*;
 pub use parser::*;
+pub use partial_json::*;
+pub use preflight::*;
 pub use process::*;
+pub use replay::*;
+pub use transcript::*;
 pub use types::*;
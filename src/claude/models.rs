@@ -0,0 +1,205 @@
+//! Known Claude models and the names the CLI accepts for them
+
+/// A model the CLI can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// Short name the CLI understands, for models that have one
+    pub alias: Option<&'static str>,
+    /// Full model ID
+    pub id: &'static str,
+    /// Display name
+    pub name: &'static str,
+    /// Context window in tokens
+    pub context_window: u64,
+}
+
+impl ModelInfo {
+    /// Name to pass to `--model`
+    pub fn cli_name(&self) -> &'static str {
+        self.alias.unwrap_or(self.id)
+    }
+
+    /// Whether `name` (an alias, a full ID or an ID without its date)
+    /// refers to this model
+    pub fn matches(&self, name: &str) -> bool {
+        let name = name.trim();
+        self.alias.is_some_and(|alias| alias.eq_ignore_ascii_case(name))
+            || self.id.eq_ignore_ascii_case(name)
+            || self.undated_id().eq_ignore_ascii_case(name)
+    }
+
+    /// The ID without its `-YYYYMMDD` release date
+    fn undated_id(&self) -> &'static str {
+        match self.id.rsplit_once('-') {
+            Some((base, date)) if date.len() == 8 && date.bytes().all(|b| b.is_ascii_digit()) => {
+                base
+            }
+            _ => self.id,
+        }
+    }
+}
+
+/// Models offered by `/model`, newest first within each family
+pub const MODELS: &[ModelInfo] = &[
+    ModelInfo {
+        alias: Some("sonnet"),
+        id: "claude-sonnet-4-5-20250929",
+        name: "Claude Sonnet 4.5",
+        context_window: 200_000,
+    },
+    ModelInfo {
+        alias: Some("opus"),
+        id: "claude-opus-4-1-20250805",
+        name: "Claude Opus 4.1",
+        context_window: 200_000,
+    },
+    ModelInfo {
+        alias: Some("haiku"),
+        id: "claude-haiku-4-5-20251001",
+        name: "Claude Haiku 4.5",
+        context_window: 200_000,
+    },
+    ModelInfo {
+        alias: Some("sonnet[1m]"),
+        id: "claude-sonnet-4-5-20250929[1m]",
+        name: "Claude Sonnet 4.5 (1M)",
+        context_window: 1_000_000,
+    },
+    ModelInfo {
+        alias: None,
+        id: "claude-sonnet-4-20250514",
+        name: "Claude Sonnet 4",
+        context_window: 200_000,
+    },
+    ModelInfo {
+        alias: None,
+        id: "claude-opus-4-20250514",
+        name: "Claude Opus 4",
+        context_window: 200_000,
+    },
+    ModelInfo {
+        alias: None,
+        id: "claude-3-7-sonnet-20250219",
+        name: "Claude Sonnet 3.7",
+        context_window: 200_000,
+    },
+    ModelInfo {
+        alias: None,
+        id: "claude-3-5-haiku-20241022",
+        name: "Claude Haiku 3.5",
+        context_window: 200_000,
+    },
+];
+
+/// Other names the CLI accepts, for which it picks the model itself
+pub const OTHER_MODEL_NAMES: &[(&str, &str)] = &[
+    ("default", "Recommended model for the account"),
+    ("opusplan", "Opus in plan mode, Sonnet otherwise"),
+];
+
+/// Context window assumed for models we don't know
+pub const DEFAULT_CONTEXT_WINDOW: u64 = 200_000;
+
+/// Context window of a model by alias or ID
+pub fn context_window(name: &str) -> u64 {
+    find_model(name).map_or(DEFAULT_CONTEXT_WINDOW, |model| model.context_window)
+}
+
+/// Look up a model by alias or ID
+pub fn find_model(name: &str) -> Option<&'static ModelInfo> {
+    MODELS.iter().find(|model| model.matches(name))
+}
+
+/// Check a model name before it reaches the CLI. Names containing
+/// `claude`, such as new releases and Bedrock or Vertex IDs, are let
+/// through. A bare name close to an alias is taken for a typo and rejected;
+/// any other name is let through with a warning, since the CLI may know it.
+pub fn validate_model(name: &str) -> Result<Option<String>, String> {
+    let name = name.trim();
+    let lowercase = name.to_ascii_lowercase();
+    if find_model(name).is_some()
+        || OTHER_MODEL_NAMES.iter().any(|(other, _)| *other == lowercase)
+        || lowercase.contains("claude")
+    {
+        return Ok(None);
+    }
+
+    let aliases = MODELS
+        .iter()
+        .filter_map(|model| model.alias)
+        .chain(OTHER_MODEL_NAMES.iter().map(|(other, _)| *other));
+    let suggestion = aliases
+        .map(|alias| (edit_distance(&lowercase, alias), alias))
+        .filter(|(distance, _)| *distance <= 2)
+        .min();
+    match suggestion {
+        Some((_, alias)) => Err(format!("Unknown model '{}' (did you mean {}?)", name, alias)),
+        None => {
+            let known: Vec<&str> = MODELS
+                .iter()
+                .map(|model| model.cli_name())
+                .chain(OTHER_MODEL_NAMES.iter().map(|(other, _)| *other))
+                .collect();
+            Ok(Some(format!(
+                "Unknown model '{}', passed to the CLI as is. Known models: {}",
+                name,
+                known.join(", ")
+            )))
+        }
+    }
+}
+
+/// Levenshtein distance, for suggesting a model after a typo
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = previous[j] + usize::from(ca != *cb);
+            current.push(substitution.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_find_model_by_alias_or_id() {
+        assert_eq!(find_model("sonnet").unwrap().id, "claude-sonnet-4-5-20250929");
+        assert_eq!(find_model("Opus").unwrap().name, "Claude Opus 4.1");
+        assert_eq!(find_model("claude-haiku-4-5").unwrap().cli_name(), "haiku");
+        assert_eq!(
+            find_model("claude-sonnet-4-20250514").unwrap().cli_name(),
+            "claude-sonnet-4-20250514"
+        );
+        assert!(find_model("gpt-4").is_none());
+    }
+
+    #[test]
+    fn test_validate_model() {
+        for name in [
+            "haiku",
+            "claude-opus-5-20270101",
+            "opusplan",
+            "sonnet[1m]",
+            "default",
+            "us.anthropic.claude-sonnet-4-20250514-v1:0",
+            "claude-sonnet-4@20250514",
+        ] {
+            assert_eq!(validate_model(name), Ok(None), "{}", name);
+        }
+        assert_eq!(
+            validate_model("sonet").unwrap_err(),
+            "Unknown model 'sonet' (did you mean sonnet?)"
+        );
+        assert!(validate_model("opusplam").unwrap_err().contains("did you mean opusplan?"));
+        let warning = validate_model("my-proxy-model").unwrap().unwrap();
+        assert!(warning.contains("Known models: sonnet, opus"));
+        assert_eq!(context_window("sonnet[1m]"), 1_000_000);
+    }
+}
@@ -0,0 +1,281 @@
+//! Options for spawning the Claude CLI
+
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::permissions::PERMISSION_TOOL;
+
+/// Settings that control how the Claude CLI is spawned
+#[derive(Debug, Clone, Default)]
+pub struct ClaudeOptions {
+    /// Claude CLI executable (defaults to `claude` on the PATH)
+    pub claude_bin: Option<PathBuf>,
+    /// Model to use
+    pub model: String,
+    /// Model the CLI switches to when the main one is overloaded
+    pub fallback_model: Option<String>,
+    /// Directory the CLI runs in (defaults to ours)
+    pub working_dir: Option<PathBuf>,
+    /// Extra directories Claude may access, for `--add-dir`
+    pub add_dirs: Vec<PathBuf>,
+    /// Continue the most recent conversation
+    pub continue_session: bool,
+    /// Resume a specific session by ID
+    pub resume_session: Option<String>,
+    /// How the CLI decides whether tools may run
+    pub permission_mode: PermissionMode,
+    /// Tools that run without asking
+    pub allowed_tools: Vec<String>,
+    /// Tools that are never allowed
+    pub disallowed_tools: Vec<String>,
+    /// Inline MCP config for the permission bridge. Without it every tool
+    /// call is allowed.
+    pub permission_bridge: Option<String>,
+    /// Replaces Claude Code's default system prompt
+    pub system_prompt: Option<String>,
+    /// Added to the end of the system prompt
+    pub append_system_prompt: Option<String>,
+    /// Standing instructions from the project's prompt file, appended
+    /// before `append_system_prompt`
+    pub project_prompt: Option<String>,
+    /// MCP config files (or inline JSON) for `--mcp-config`
+    pub mcp_configs: Vec<String>,
+    /// Use only the servers from `mcp_configs` (and the permission bridge)
+    pub strict_mcp_config: bool,
+    /// Agentic turns allowed per message, for `--max-turns`
+    pub max_turns: Option<u32>,
+}
+
+/// Project prompt file, relative to the project directory
+pub const PROJECT_PROMPT_FILE: &str = ".claude-terminal/prompt.md";
+
+/// Read a project's prompt file, if it has one with any content
+pub fn load_project_prompt(project_dir: &Path) -> Option<String> {
+    std::fs::read_to_string(project_dir.join(PROJECT_PROMPT_FILE))
+        .ok()
+        .map(|prompt| prompt.trim().to_string())
+        .filter(|prompt| !prompt.is_empty())
+}
+
+/// Permission modes understood by the CLI's `--permission-mode`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, clap::ValueEnum)]
+pub enum PermissionMode {
+    /// Ask before running tools
+    #[default]
+    #[value(name = "default")]
+    Default,
+    /// Plan only, no edits or commands
+    #[value(name = "plan")]
+    Plan,
+    /// Apply file edits without asking
+    #[value(name = "acceptEdits")]
+    AcceptEdits,
+    /// Run everything without asking
+    #[value(name = "bypassPermissions")]
+    BypassPermissions,
+}
+
+impl PermissionMode {
+    pub const ALL: [PermissionMode; 4] = [
+        PermissionMode::Default,
+        PermissionMode::Plan,
+        PermissionMode::AcceptEdits,
+        PermissionMode::BypassPermissions,
+    ];
+
+    /// Name as passed to the CLI
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PermissionMode::Default => "default",
+            PermissionMode::Plan => "plan",
+            PermissionMode::AcceptEdits => "acceptEdits",
+            PermissionMode::BypassPermissions => "bypassPermissions",
+        }
+    }
+
+    /// Parse a mode name (case-insensitive)
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|mode| mode.as_str().eq_ignore_ascii_case(name))
+    }
+
+    /// The next mode in the Shift+Tab cycle
+    pub fn next(self) -> Self {
+        let index = Self::ALL.iter().position(|m| *m == self).unwrap_or(0);
+        Self::ALL[(index + 1) % Self::ALL.len()]
+    }
+}
+
+impl ClaudeOptions {
+    /// Executable to spawn
+    pub fn program(&self) -> &Path {
+        self.claude_bin.as_deref().unwrap_or(Path::new("claude"))
+    }
+
+    /// Everything added to the end of the system prompt
+    pub fn appended_prompt(&self) -> Option<String> {
+        let parts: Vec<&str> = [&self.project_prompt, &self.append_system_prompt]
+            .into_iter()
+            .filter_map(|part| part.as_deref())
+            .collect();
+        (!parts.is_empty()).then(|| parts.join("\n\n"))
+    }
+
+    /// Add the CLI arguments for these options
+    pub fn apply(&self, cmd: &mut Command) {
+        cmd.arg("--model");
+        cmd.arg(&self.model);
+        if let Some(model) = &self.fallback_model {
+            cmd.arg("--fallback-model");
+            cmd.arg(model);
+        }
+
+        // Directories
+        if let Some(dir) = &self.working_dir {
+            cmd.current_dir(dir);
+        }
+        if !self.add_dirs.is_empty() {
+            cmd.arg("--add-dir");
+            cmd.args(&self.add_dirs);
+        }
+
+        // System prompt
+        if let Some(prompt) = &self.system_prompt {
+            cmd.arg("--system-prompt");
+            cmd.arg(prompt);
+        }
+        if let Some(prompt) = self.appended_prompt() {
+            cmd.arg("--append-system-prompt");
+            cmd.arg(prompt);
+        }
+
+        // MCP servers: the user's configs plus the permission bridge
+        let bridge = self
+            .permission_bridge
+            .as_ref()
+            .filter(|_| self.permission_mode != PermissionMode::BypassPermissions);
+        let mcp_configs: Vec<&String> = self.mcp_configs.iter().chain(bridge).collect();
+        if !mcp_configs.is_empty() {
+            cmd.arg("--mcp-config");
+            cmd.args(mcp_configs);
+        }
+        if self.strict_mcp_config {
+            cmd.arg("--strict-mcp-config");
+        }
+
+        // Permission handling
+        if bridge.is_some() {
+            cmd.arg("--permission-mode");
+            cmd.arg(self.permission_mode.as_str());
+            cmd.arg("--permission-prompt-tool");
+            cmd.arg(PERMISSION_TOOL);
+        } else {
+            cmd.arg("--dangerously-skip-permissions");
+        }
+        if !self.allowed_tools.is_empty() {
+            cmd.arg("--allowedTools");
+            cmd.args(&self.allowed_tools);
+        }
+        if !self.disallowed_tools.is_empty() {
+            cmd.arg("--disallowedTools");
+            cmd.args(&self.disallowed_tools);
+        }
+
+        if let Some(max_turns) = self.max_turns {
+            cmd.arg("--max-turns");
+            cmd.arg(max_turns.to_string());
+        }
+
+        // Session handling
+        if let Some(session_id) = &self.resume_session {
+            cmd.arg("--resume");
+            cmd.arg(session_id);
+        } else if self.continue_session {
+            cmd.arg("--continue");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_appended_prompt_combines_project_and_flag() {
+        let mut options = ClaudeOptions::default();
+        assert_eq!(options.appended_prompt(), None);
+
+        options.append_system_prompt = Some("Be terse.".to_string());
+        assert_eq!(options.appended_prompt().as_deref(), Some("Be terse."));
+
+        options.project_prompt = Some("Use tabs.".to_string());
+        assert_eq!(options.appended_prompt().as_deref(), Some("Use tabs.\n\nBe terse."));
+    }
+
+    #[test]
+    fn test_permission_arguments_for_each_mode() {
+        let args = |options: &ClaudeOptions| {
+            let mut cmd = Command::new("claude");
+            options.apply(&mut cmd);
+            let args: Vec<String> = cmd
+                .as_std()
+                .get_args()
+                .map(|arg| arg.to_string_lossy().into_owned())
+                .collect();
+            args.join(" ")
+        };
+        let mut options = ClaudeOptions {
+            model: "sonnet".to_string(),
+            allowed_tools: vec!["Read".to_string()],
+            disallowed_tools: vec!["Bash(rm:*)".to_string()],
+            ..Default::default()
+        };
+        let tools = "--allowedTools Read --disallowedTools Bash(rm:*)";
+
+        // Without the bridge nothing can ask, so every mode skips the checks
+        for mode in PermissionMode::ALL {
+            options.permission_mode = mode;
+            assert_eq!(
+                args(&options),
+                format!("--model sonnet --dangerously-skip-permissions {}", tools)
+            );
+        }
+
+        options.permission_bridge = Some("{}".to_string());
+        for mode in [PermissionMode::Default, PermissionMode::Plan, PermissionMode::AcceptEdits] {
+            options.permission_mode = mode;
+            assert_eq!(
+                args(&options),
+                format!(
+                    "--model sonnet --mcp-config {{}} --permission-mode {} \
+                     --permission-prompt-tool {} {}",
+                    mode.as_str(),
+                    PERMISSION_TOOL,
+                    tools
+                )
+            );
+        }
+
+        // Bypassing permissions leaves the bridge out
+        options.permission_mode = PermissionMode::BypassPermissions;
+        assert_eq!(
+            args(&options),
+            format!("--model sonnet --dangerously-skip-permissions {}", tools)
+        );
+    }
+
+    #[test]
+    fn test_load_project_prompt() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        assert_eq!(load_project_prompt(dir), None);
+
+        std::fs::create_dir_all(dir.join(".claude-terminal")).unwrap();
+        std::fs::write(dir.join(PROJECT_PROMPT_FILE), "  \n").unwrap();
+        assert_eq!(load_project_prompt(dir), None);
+
+        std::fs::write(dir.join(PROJECT_PROMPT_FILE), "Run cargo fmt.\n").unwrap();
+        assert_eq!(load_project_prompt(dir).as_deref(), Some("Run cargo fmt."));
+    }
+}
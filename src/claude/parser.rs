@@ -1,15 +1,23 @@
 //! Parser for Claude CLI stream-json output
 
 use anyhow::Result;
+use std::collections::HashMap;
 
-use super::types::{ContentBlock, ContentDelta, RawStreamEvent, StreamEvent};
+use super::types::{
+    ClaudeMessage, CompactMetadata, ContentBlock, ContentDelta, RawStreamEvent, SessionInit,
+    StreamEvent,
+};
 
 /// Parser state for accumulating tool use inputs
 #[derive(Debug, Default)]
 pub struct StreamParser {
-    /// Current tool use being accumulated
-    current_tool_name: Option<String>,
+    /// Current tool use whose input is streaming in
+    current_tool_id: String,
     current_tool_input: String,
+    /// Message already shown through partial stream events
+    streamed_message_id: Option<String>,
+    /// Tool names by tool_use id, for labelling results
+    tool_names: HashMap<String, String>,
 }
 
 impl StreamParser {
@@ -36,24 +44,43 @@ impl StreamParser {
     }
 
     fn process_event(&mut self, event: RawStreamEvent) -> Result<Vec<StreamEvent>> {
+        let parent_tool_use_id = match &event {
+            RawStreamEvent::Assistant { parent_tool_use_id, .. }
+            | RawStreamEvent::User { parent_tool_use_id, .. }
+            | RawStreamEvent::StreamEvent { parent_tool_use_id, .. } => parent_tool_use_id.clone(),
+            _ => None,
+        };
+
+        let events = self.translate_event(event)?;
+
+        // Subagent output is tagged with the Task call that spawned it
+        Ok(match parent_tool_use_id {
+            Some(parent) => events
+                .into_iter()
+                .map(|event| StreamEvent::Subagent {
+                    parent_tool_use_id: parent.clone(),
+                    event: Box::new(event),
+                })
+                .collect(),
+            None => events,
+        })
+    }
+
+    fn translate_event(&mut self, event: RawStreamEvent) -> Result<Vec<StreamEvent>> {
         let mut events = Vec::new();
 
         match event {
-            RawStreamEvent::Assistant { message } | RawStreamEvent::MessageStart { message } => {
-                // Process any content blocks in the message
-                if let Some(content) = message.content {
-                    for block in content {
-                        events.extend(self.process_content_block(block)?);
-                    }
-                }
-                // Process usage
-                if let Some(usage) = message.usage {
-                    events.push(StreamEvent::Usage {
-                        input_tokens: usage.input_tokens,
-                        output_tokens: usage.output_tokens,
-                        cache_read_tokens: usage.cache_read_input_tokens,
-                        cache_write_tokens: usage.cache_creation_input_tokens,
-                    });
+            RawStreamEvent::StreamEvent { event, .. } => {
+                events.extend(self.translate_event(*event)?);
+            }
+            RawStreamEvent::MessageStart { message } => {
+                self.streamed_message_id = message.id.clone();
+                events.extend(self.process_message(message)?);
+            }
+            RawStreamEvent::Assistant { message, .. } => {
+                // The complete message repeats what was already streamed
+                if message.id.is_none() || message.id != self.streamed_message_id {
+                    events.extend(self.process_message(message)?);
                 }
             }
             RawStreamEvent::ContentBlockStart { content_block, .. } => {
@@ -65,8 +92,14 @@ impl StreamParser {
                         events.push(StreamEvent::Text(text));
                     }
                     ContentDelta::InputJsonDelta { partial_json } => {
-                        // Accumulate tool input
+                        // Accumulate tool input and pass it on for live display
                         self.current_tool_input.push_str(&partial_json);
+                        if !self.current_tool_id.is_empty() {
+                            events.push(StreamEvent::ToolInput {
+                                id: self.current_tool_id.clone(),
+                                input: self.current_tool_input.clone(),
+                            });
+                        }
                     }
                     ContentDelta::ThinkingDelta { thinking } => {
                         events.push(StreamEvent::Thinking(thinking));
@@ -75,10 +108,15 @@ impl StreamParser {
                 }
             }
             RawStreamEvent::ContentBlockStop { .. } => {
-                // Finalize tool use if we were accumulating one
-                if let Some(name) = self.current_tool_name.take() {
-                    let input = std::mem::take(&mut self.current_tool_input);
-                    events.push(StreamEvent::ToolUse { name, input });
+                // Finalize tool input if we were accumulating one
+                let id = std::mem::take(&mut self.current_tool_id);
+                let input = std::mem::take(&mut self.current_tool_input);
+                if !id.is_empty() && !input.is_empty() {
+                    let input = serde_json::from_str::<serde_json::Value>(&input)
+                        .ok()
+                        .and_then(|value| serde_json::to_string_pretty(&value).ok())
+                        .unwrap_or(input);
+                    events.push(StreamEvent::ToolInput { id, input });
                 }
             }
             RawStreamEvent::MessageDelta { usage, .. } => {
@@ -91,7 +129,16 @@ impl StreamParser {
                     });
                 }
             }
-            RawStreamEvent::Result { result, subtype, .. } => {
+            RawStreamEvent::Result {
+                result,
+                subtype,
+                is_error,
+                total_cost_usd,
+                duration_ms,
+                duration_api_ms,
+                num_turns,
+                ..
+            } => {
                 // Handle tool results
                 if subtype.as_deref() == Some("tool_result") {
                     if let Some(result_data) = result {
@@ -101,21 +148,82 @@ impl StreamParser {
                             serde_json::to_string_pretty(&result_data).unwrap_or_default()
                         };
                         events.push(StreamEvent::ToolResult {
+                            id: String::new(),
                             name: "tool".to_string(),
                             result: result_str,
+                            is_error: is_error.unwrap_or_default(),
+                        });
+                    }
+                } else {
+                    // Any other result marks the end of the turn
+                    events.push(StreamEvent::TurnComplete {
+                        cost_usd: total_cost_usd.unwrap_or_default(),
+                        duration_ms: duration_ms.unwrap_or_default(),
+                        duration_api_ms: duration_api_ms.unwrap_or_default(),
+                        num_turns: num_turns.unwrap_or_default(),
+                        is_error: is_error.unwrap_or_default(),
+                    });
+                }
+            }
+            RawStreamEvent::System { subtype, data } => {
+                match subtype.as_deref() {
+                    Some("init") => match serde_json::from_value::<SessionInit>(data) {
+                        Ok(init) => events.push(StreamEvent::SessionInit(init)),
+                        Err(e) => tracing::debug!("Failed to parse init event: {}", e),
+                    },
+                    Some("compact_boundary") => {
+                        let metadata = data
+                            .get("compact_metadata")
+                            .cloned()
+                            .and_then(|m| serde_json::from_value::<CompactMetadata>(m).ok())
+                            .unwrap_or_default();
+                        events.push(StreamEvent::Compacted {
+                            trigger: metadata.trigger,
+                            pre_tokens: metadata.pre_tokens,
                         });
                     }
+                    _ => {}
                 }
             }
-            RawStreamEvent::System { .. }
-            | RawStreamEvent::User { .. }
-            | RawStreamEvent::MessageStop
+            RawStreamEvent::User { message, .. } => {
+                // Tool results come back to Claude as user messages
+                if let Some(content) = message.content {
+                    for block in content {
+                        if matches!(block, ContentBlock::ToolResult { .. }) {
+                            events.extend(self.process_content_block(block)?);
+                        }
+                    }
+                }
+            }
+            RawStreamEvent::MessageStop
             | RawStreamEvent::Unknown => {}
         }
 
         Ok(events)
     }
 
+    fn process_message(&mut self, message: ClaudeMessage) -> Result<Vec<StreamEvent>> {
+        let mut events = Vec::new();
+
+        // Process any content blocks in the message
+        if let Some(content) = message.content {
+            for block in content {
+                events.extend(self.process_content_block(block)?);
+            }
+        }
+        // Process usage
+        if let Some(usage) = message.usage {
+            events.push(StreamEvent::Usage {
+                input_tokens: usage.input_tokens,
+                output_tokens: usage.output_tokens,
+                cache_read_tokens: usage.cache_read_input_tokens,
+                cache_write_tokens: usage.cache_creation_input_tokens,
+            });
+        }
+
+        Ok(events)
+    }
+
     fn process_content_block(&mut self, block: ContentBlock) -> Result<Vec<StreamEvent>> {
         let mut events = Vec::new();
 
@@ -123,26 +231,29 @@ impl StreamParser {
             ContentBlock::Text { text } => {
                 events.push(StreamEvent::Text(text));
             }
-            ContentBlock::ToolUse { name, input, .. } => {
-                // Store the tool name, we'll emit the event when we get all the input
-                self.current_tool_name = Some(name.clone());
-                self.current_tool_input = serde_json::to_string_pretty(&input).unwrap_or_default();
-                // If the input is already complete, emit now
-                if !self.current_tool_input.is_empty() {
-                    let input = std::mem::take(&mut self.current_tool_input);
-                    self.current_tool_name = None;
-                    events.push(StreamEvent::ToolUse { name, input });
-                }
+            ContentBlock::ToolUse { id, name, input } => {
+                self.tool_names.insert(id.clone(), name.clone());
+                // Streamed tool calls start with empty input that fills in
+                // through input_json_delta events
+                self.current_tool_id = id.clone();
+                self.current_tool_input.clear();
+                let input = serde_json::to_string_pretty(&input).unwrap_or_default();
+                events.push(StreamEvent::ToolUse { id, name, input });
             }
-            ContentBlock::ToolResult { content, .. } => {
-                let result = if let Some(s) = content.as_str() {
-                    s.to_string()
-                } else {
-                    serde_json::to_string_pretty(&content).unwrap_or_default()
-                };
+            ContentBlock::ToolResult {
+                tool_use_id,
+                content,
+                is_error,
+            } => {
+                let name = self
+                    .tool_names
+                    .remove(&tool_use_id)
+                    .unwrap_or_else(|| "tool".to_string());
                 events.push(StreamEvent::ToolResult {
-                    name: "tool".to_string(),
-                    result,
+                    id: tool_use_id,
+                    name,
+                    result: tool_result_text(&content),
+                    is_error,
                 });
             }
             ContentBlock::Thinking { thinking } => {
@@ -154,3 +265,25 @@ impl StreamParser {
         Ok(events)
     }
 }
+
+/// Flatten tool result content (a string or a list of content blocks) to text
+fn tool_result_text(content: &serde_json::Value) -> String {
+    match content {
+        serde_json::Value::String(s) => s.clone(),
+        serde_json::Value::Array(blocks) => blocks
+            .iter()
+            .map(|block| match block.get("type").and_then(|t| t.as_str()) {
+                Some("text") => block
+                    .get("text")
+                    .and_then(|t| t.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                Some(other) => format!("[{}]", other),
+                None => block.to_string(),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        serde_json::Value::Null => String::new(),
+        other => serde_json::to_string_pretty(other).unwrap_or_default(),
+    }
+}
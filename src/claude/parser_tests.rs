@@ -40,12 +40,44 @@ mod tests {
     }
 
     #[test]
-    fn test_parse_system_event_ignored() {
+    fn test_parse_system_init() {
         let mut parser = StreamParser::new();
-        let line = r#"{"type":"system","subtype":"init","session_id":"abc"}"#;
+        let line = r#"{"type":"system","subtype":"init","session_id":"abc","model":"claude-sonnet-4-5","cwd":"/tmp","tools":["Read","Bash"],"mcp_servers":[{"name":"github","status":"connected"}]}"#;
 
         let events = parser.parse_line(line).unwrap();
-        assert!(events.is_empty(), "System events should be ignored");
+
+        assert_eq!(events.len(), 1);
+        let StreamEvent::SessionInit(init) = &events[0] else {
+            panic!("Expected SessionInit, got {:?}", events[0]);
+        };
+        assert_eq!(init.session_id, "abc");
+        assert_eq!(init.model, "claude-sonnet-4-5");
+        assert_eq!(init.tools, vec!["Read", "Bash"]);
+        assert_eq!(init.mcp_servers[0].name, "github");
+        assert_eq!(init.mcp_servers[0].status, "connected");
+    }
+
+    #[test]
+    fn test_parse_other_system_event_ignored() {
+        let mut parser = StreamParser::new();
+        let line = r#"{"type":"system","subtype":"hook_response","session_id":"abc"}"#;
+
+        let events = parser.parse_line(line).unwrap();
+        assert!(events.is_empty(), "Unknown system events should be ignored");
+    }
+
+    #[test]
+    fn test_parse_compact_boundary() {
+        let mut parser = StreamParser::new();
+        let line = r#"{"type":"system","subtype":"compact_boundary","session_id":"abc","compact_metadata":{"trigger":"manual","pre_tokens":150000}}"#;
+
+        let events = parser.parse_line(line).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            StreamEvent::Compacted { trigger, pre_tokens: Some(150000) } if trigger == "manual"
+        ));
     }
 
     #[test]
@@ -110,4 +142,129 @@ mod tests {
         assert!(matches!(&events2[0], StreamEvent::Text(t) if t == " "));
         assert!(matches!(&events3[0], StreamEvent::Text(t) if t == "World"));
     }
+
+    #[test]
+    fn test_result_event_completes_turn() {
+        let mut parser = StreamParser::new();
+        let line = r#"{"type":"result","subtype":"success","is_error":false,"duration_ms":4200,"duration_api_ms":3800,"num_turns":3,"result":"Done","session_id":"abc","total_cost_usd":0.0312}"#;
+
+        let events = parser.parse_line(line).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            StreamEvent::TurnComplete {
+                cost_usd,
+                duration_ms: 4200,
+                duration_api_ms: 3800,
+                num_turns: 3,
+                is_error: false,
+            } if (*cost_usd - 0.0312).abs() < f64::EPSILON
+        ));
+    }
+
+    #[test]
+    fn test_tool_result_paired_with_tool_use() {
+        let mut parser = StreamParser::new();
+        let tool_use = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"ls"}}]}}"#;
+        let tool_result = r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"a.txt\nb.txt","is_error":false}]}}"#;
+
+        let events = parser.parse_line(tool_use).unwrap();
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, StreamEvent::ToolUse { id, name, .. } if id == "toolu_1" && name == "Bash")));
+
+        let events = parser.parse_line(tool_result).unwrap();
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            StreamEvent::ToolResult { id, name, result, is_error: false }
+                if id == "toolu_1" && name == "Bash" && result == "a.txt\nb.txt"
+        ));
+    }
+
+    #[test]
+    fn test_tool_result_error_with_block_content() {
+        let mut parser = StreamParser::new();
+        let line = r#"{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_2","content":[{"type":"text","text":"File not found"}],"is_error":true}]}}"#;
+
+        let events = parser.parse_line(line).unwrap();
+
+        assert_eq!(events.len(), 1);
+        assert!(matches!(
+            &events[0],
+            StreamEvent::ToolResult { id, result, is_error: true, .. }
+                if id == "toolu_2" && result == "File not found"
+        ));
+    }
+
+    #[test]
+    fn test_streamed_tool_input() {
+        let mut parser = StreamParser::new();
+        let lines = [
+            r#"{"type":"stream_event","event":{"type":"message_start","message":{"id":"msg_1","content":[]}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_start","index":0,"content_block":{"type":"tool_use","id":"toolu_1","name":"Write","input":{}}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"{\"file_path\":\"a.rs\","}}}"#,
+            r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"\"content\":\"fn"}}}"#,
+        ];
+        let events: Vec<_> = lines.iter().flat_map(|l| parser.parse_line(l).unwrap()).collect();
+
+        assert!(events.iter().any(|e| matches!(e, StreamEvent::ToolUse { id, .. } if id == "toolu_1")));
+        let last_input = events.iter().rev().find_map(|e| match e {
+            StreamEvent::ToolInput { id, input } if id == "toolu_1" => Some(input.clone()),
+            _ => None,
+        });
+        assert_eq!(last_input.as_deref(), Some(r#"{"file_path":"a.rs","content":"fn"#));
+
+        // Closing the block emits the complete input
+        parser
+            .parse_line(r#"{"type":"stream_event","event":{"type":"content_block_delta","index":0,"delta":{"type":"input_json_delta","partial_json":"\"}"}}}"#)
+            .unwrap();
+        let events = parser
+            .parse_line(r#"{"type":"stream_event","event":{"type":"content_block_stop","index":0}}"#)
+            .unwrap();
+        assert!(matches!(&events[0], StreamEvent::ToolInput { input, .. } if input.contains("\"content\": \"fn\"")));
+    }
+
+    #[test]
+    fn test_streamed_message_not_repeated() {
+        let mut parser = StreamParser::new();
+        parser
+            .parse_line(r#"{"type":"stream_event","event":{"type":"message_start","message":{"id":"msg_1","content":[]}}}"#)
+            .unwrap();
+
+        let events = parser
+            .parse_line(r#"{"type":"assistant","message":{"id":"msg_1","content":[{"type":"text","text":"Hello"}]}}"#)
+            .unwrap();
+        assert!(events.is_empty(), "Streamed message content should not be emitted twice");
+
+        let events = parser
+            .parse_line(r#"{"type":"assistant","message":{"id":"msg_2","content":[{"type":"text","text":"Hi"}]}}"#)
+            .unwrap();
+        assert!(matches!(&events[0], StreamEvent::Text(t) if t == "Hi"));
+    }
+
+    #[test]
+    fn test_subagent_events_tagged_with_parent() {
+        let mut parser = StreamParser::new();
+        let line = r#"{"type":"assistant","message":{"content":[{"type":"tool_use","id":"toolu_child","name":"Grep","input":{"pattern":"fn main"}}]},"parent_tool_use_id":"toolu_task"}"#;
+
+        let events = parser.parse_line(line).unwrap();
+
+        assert_eq!(events.len(), 1);
+        let StreamEvent::Subagent { parent_tool_use_id, event } = &events[0] else {
+            panic!("Expected Subagent, got {:?}", events[0]);
+        };
+        assert_eq!(parent_tool_use_id, "toolu_task");
+        assert!(matches!(event.as_ref(), StreamEvent::ToolUse { id, name, .. } if id == "toolu_child" && name == "Grep"));
+    }
+
+    #[test]
+    fn test_main_agent_events_not_tagged() {
+        let mut parser = StreamParser::new();
+        let line = r#"{"type":"assistant","message":{"content":[{"type":"text","text":"Hi"}]},"parent_tool_use_id":null}"#;
+
+        let events = parser.parse_line(line).unwrap();
+        assert!(matches!(&events[0], StreamEvent::Text(t) if t == "Hi"));
+    }
 }
@@ -0,0 +1,209 @@
+//! Tolerant reader for tool input JSON that is still streaming in
+
+/// Fields worth showing while a tool call's input streams, in display order
+pub const KEY_FIELDS: &[&str] = &[
+    "file_path",
+    "path",
+    "notebook_path",
+    "command",
+    "pattern",
+    "url",
+    "query",
+    "description",
+    "prompt",
+    "old_string",
+    "new_string",
+    "content",
+];
+
+/// A top-level string field read from partial JSON
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialField {
+    pub key: String,
+    pub value: String,
+    /// False while the closing quote hasn't arrived yet
+    pub complete: bool,
+}
+
+/// Read the top-level string fields of a JSON object that may be cut off at
+/// any point. Non-string values are skipped; an unfinished string is returned
+/// with what has arrived so far.
+pub fn extract_string_fields(partial: &str) -> Vec<PartialField> {
+    let mut fields = Vec::new();
+    let mut chars = partial.chars().peekable();
+
+    skip_whitespace(&mut chars);
+    if chars.next() != Some('{') {
+        return fields;
+    }
+
+    loop {
+        skip_whitespace(&mut chars);
+        match chars.next() {
+            Some('"') => {}
+            Some(',') => continue,
+            _ => break,
+        }
+        let Some((key, true)) = read_string(&mut chars) else {
+            break;
+        };
+
+        skip_whitespace(&mut chars);
+        if chars.next() != Some(':') {
+            break;
+        }
+        skip_whitespace(&mut chars);
+
+        match chars.peek() {
+            Some('"') => {
+                chars.next();
+                let Some((value, complete)) = read_string(&mut chars) else {
+                    break;
+                };
+                fields.push(PartialField { key, value, complete });
+                if !complete {
+                    break;
+                }
+            }
+            Some(_) => {
+                if !skip_value(&mut chars) {
+                    break;
+                }
+            }
+            None => break,
+        }
+    }
+
+    fields
+}
+
+/// Pick the fields from `KEY_FIELDS` present in a tool input, in display order
+pub fn key_fields(partial: &str) -> Vec<PartialField> {
+    let fields = extract_string_fields(partial);
+    KEY_FIELDS
+        .iter()
+        .filter_map(|key| fields.iter().find(|f| f.key == *key).cloned())
+        .collect()
+}
+
+type Chars<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+
+fn skip_whitespace(chars: &mut Chars) {
+    while chars.peek().is_some_and(|c| c.is_whitespace()) {
+        chars.next();
+    }
+}
+
+/// Read a string body after its opening quote. Returns the text and whether
+/// the closing quote was seen; `None` only for a cut-off escape at the start.
+fn read_string(chars: &mut Chars) -> Option<(String, bool)> {
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => return Some((out, true)),
+            '\\' => match chars.next() {
+                Some('n') => out.push('\n'),
+                Some('t') => out.push('\t'),
+                Some('r') => out.push('\r'),
+                Some('b') => out.push('\u{8}'),
+                Some('f') => out.push('\u{c}'),
+                Some('u') => {
+                    let hex: String = chars.by_ref().take(4).collect();
+                    if hex.len() < 4 {
+                        return Some((out, false));
+                    }
+                    if let Some(c) = u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32) {
+                        out.push(c);
+                    }
+                }
+                Some(other) => out.push(other),
+                None => return Some((out, false)),
+            },
+            c => out.push(c),
+        }
+    }
+    Some((out, false))
+}
+
+/// Skip a non-string value (number, literal, object or array). Returns false
+/// if the input ended first.
+fn skip_value(chars: &mut Chars) -> bool {
+    let mut depth = 0usize;
+    while let Some(&c) = chars.peek() {
+        match c {
+            '"' => {
+                chars.next();
+                if !matches!(read_string(chars), Some((_, true))) {
+                    return false;
+                }
+                continue;
+            }
+            '{' | '[' => depth += 1,
+            '}' | ']' if depth > 0 => depth -= 1,
+            ',' | '}' if depth == 0 => return true,
+            _ => {}
+        }
+        chars.next();
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn field(key: &str, value: &str, complete: bool) -> PartialField {
+        PartialField {
+            key: key.to_string(),
+            value: value.to_string(),
+            complete,
+        }
+    }
+
+    #[test]
+    fn test_complete_object() {
+        let fields = extract_string_fields(r#"{"file_path": "src/main.rs", "limit": 20, "content": "a\nb"}"#);
+        assert_eq!(
+            fields,
+            vec![field("file_path", "src/main.rs", true), field("content", "a\nb", true)]
+        );
+    }
+
+    #[test]
+    fn test_cut_off_inside_value() {
+        let fields = extract_string_fields(r#"{"file_path":"src/lib.rs","content":"fn main() {\n    pri"#);
+        assert_eq!(
+            fields,
+            vec![
+                field("file_path", "src/lib.rs", true),
+                field("content", "fn main() {\n    pri", false),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cut_off_inside_key_or_escape() {
+        assert_eq!(extract_string_fields(r#"{"comm"#), vec![]);
+        assert_eq!(extract_string_fields(r#"{"command":"echo \"#), vec![field("command", "echo ", false)]);
+        assert_eq!(extract_string_fields(r#"{"command":"caf\u00"#), vec![field("command", "caf", false)]);
+    }
+
+    #[test]
+    fn test_skips_nested_values() {
+        let fields = extract_string_fields(r#"{"edits":[{"old_string":"x"}],"file_path":"a.rs"}"#);
+        assert_eq!(fields, vec![field("file_path", "a.rs", true)]);
+    }
+
+    #[test]
+    fn test_not_an_object() {
+        assert!(extract_string_fields("").is_empty());
+        assert!(extract_string_fields("[1,2]").is_empty());
+    }
+
+    #[test]
+    fn test_key_fields_order() {
+        let fields = key_fields(r#"{"content":"x","file_path":"a.rs","other":"y"}"#);
+        let keys: Vec<_> = fields.iter().map(|f| f.key.as_str()).collect();
+        assert_eq!(keys, vec!["file_path", "content"]);
+    }
+}
@@ -0,0 +1,151 @@
+//! Startup checks for the Claude CLI
+
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+
+use super::ClaudeOptions;
+
+/// Flags we pass to every Claude process; older CLIs lack some of them
+const REQUIRED_FLAGS: &[&str] = &[
+    "--input-format",
+    "--include-partial-messages",
+    "--permission-prompt-tool",
+];
+
+/// How long to wait for `claude --version` / `--help`
+const CHECK_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Result of checking the Claude CLI before first use
+#[derive(Debug, Clone, Default)]
+pub struct PreflightReport {
+    /// Output of `claude --version`, if it ran
+    pub version: Option<String>,
+    /// Problems found, each with guidance on fixing it
+    pub problems: Vec<String>,
+}
+
+/// Check that the CLI runs, supports the flags we use, and looks logged in
+pub async fn preflight(options: &ClaudeOptions) -> PreflightReport {
+    let mut report = PreflightReport::default();
+    let program = options.program().display().to_string();
+
+    let version = match run(options, "--version").await {
+        Ok(output) => output,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => {
+            report.problems.push(format!(
+                "Claude CLI not found ({}). Install it with \
+                 `npm install -g @anthropic-ai/claude-code` or point --claude-bin at it.",
+                program
+            ));
+            return report;
+        }
+        Err(e) => {
+            report.problems.push(format!("Could not run `{} --version`: {}", program, e));
+            return report;
+        }
+    };
+    report.version = Some(version.trim().to_string()).filter(|v| !v.is_empty());
+
+    match run(options, "--help").await {
+        Ok(help) => {
+            let missing = missing_flags(&help);
+            if !missing.is_empty() {
+                report.problems.push(format!(
+                    "Claude CLI {} does not support {}. Update it with `claude update`.",
+                    report.version.as_deref().unwrap_or("(unknown version)"),
+                    missing.join(", ")
+                ));
+            }
+        }
+        Err(e) => report.problems.push(format!("Could not run `{} --help`: {}", program, e)),
+    }
+
+    if !auth_configured() {
+        report.problems.push(
+            "Claude CLI does not appear to be logged in. Run `claude` once and use /login, \
+             or set ANTHROPIC_API_KEY."
+                .to_string(),
+        );
+    }
+
+    report
+}
+
+/// Run the CLI with a single argument and return its stdout
+async fn run(options: &ClaudeOptions, arg: &str) -> std::io::Result<String> {
+    let output = Command::new(options.program())
+        .arg(arg)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(CHECK_TIMEOUT, output)
+        .await
+        .map_err(|_| std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out"))??;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(std::io::Error::other(format!("{} {}", output.status, stderr.trim())));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Required flags missing from `claude --help` output
+fn missing_flags(help: &str) -> Vec<&'static str> {
+    REQUIRED_FLAGS
+        .iter()
+        .copied()
+        .filter(|flag| !help.contains(flag))
+        .collect()
+}
+
+/// Best-effort check for credentials the CLI can use. Logins store an
+/// account in ~/.claude.json even when the token lives in a keychain.
+fn auth_configured() -> bool {
+    const AUTH_ENV: &[&str] = &[
+        "ANTHROPIC_API_KEY",
+        "ANTHROPIC_AUTH_TOKEN",
+        "CLAUDE_CODE_OAUTH_TOKEN",
+        "CLAUDE_CODE_USE_BEDROCK",
+        "CLAUDE_CODE_USE_VERTEX",
+    ];
+    if AUTH_ENV.iter().any(|var| std::env::var_os(var).is_some_and(|v| !v.is_empty())) {
+        return true;
+    }
+
+    let Some(home) = dirs::home_dir() else {
+        return true;
+    };
+    if home.join(".claude").join(".credentials.json").exists() {
+        return true;
+    }
+    std::fs::read_to_string(home.join(".claude.json"))
+        .ok()
+        .and_then(|config| serde_json::from_str::<serde_json::Value>(&config).ok())
+        .is_some_and(|config| config.get("oauthAccount").is_some_and(|a| !a.is_null()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_flags() {
+        let help = "Options:\n  --input-format <format>\n  --permission-prompt-tool <tool>\n";
+        assert_eq!(missing_flags(help), vec!["--include-partial-messages"]);
+        assert!(missing_flags(&REQUIRED_FLAGS.join("\n")).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_preflight_reports_missing_binary() {
+        let options = ClaudeOptions {
+            claude_bin: Some("/nonexistent/claude".into()),
+            ..Default::default()
+        };
+
+        let report = preflight(&options).await;
+
+        assert!(report.version.is_none());
+        assert_eq!(report.problems.len(), 1);
+        assert!(report.problems[0].contains("not found"));
+    }
+}
@@ -1,82 +1,79 @@
 //! Claude CLI process management
 
 use anyhow::Result;
-use std::process::Stdio;
+use std::collections::VecDeque;
+use std::process::{ExitStatus, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
 use tokio::{
     io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
-    process::{Child, Command},
-    sync::mpsc,
+    process::{Child, ChildStdin, Command},
+    sync::{mpsc, oneshot},
+    task::JoinHandle,
 };
 
-use crate::app::AppMessage;
+use crate::app::{AppMessage, RequestId};
 
-use super::{StreamEvent, StreamParser};
+use super::{ClaudeOptions, InputMessage, StreamParser, StreamRecorder};
 
-/// Manages a Claude CLI process
+/// Stderr lines included in the error for a failed exit
+const STDERR_TAIL_LINES: usize = 10;
+
+/// How long Claude gets to exit after SIGINT before it is killed
+const INTERRUPT_GRACE: Duration = Duration::from_secs(3);
+
+/// Manages a long-lived Claude CLI process for one conversation
 pub struct ClaudeProcess {
-    child: Child,
-    message_tx: mpsc::Sender<AppMessage>,
-    aborted: bool,
+    /// Tags every message from this process
+    id: RequestId,
+    stdin: Option<ChildStdin>,
+    /// Asks the supervisor task to interrupt the process. Dropping it
+    /// kills the process outright.
+    interrupt_tx: Option<oneshot::Sender<()>>,
+    /// Set once interrupted; later output is no longer forwarded
+    interrupted: Arc<AtomicBool>,
+    recorder: Option<StreamRecorder>,
 }
 
 impl ClaudeProcess {
-    /// Start a new Claude process
+    /// Start a new Claude process and begin reading its output, tagging
+    /// messages with `id`. With a recorder, every raw stdout line and sent
+    /// message is also recorded.
     pub fn new(
-        model: &str,
+        id: RequestId,
+        options: &ClaudeOptions,
+        recorder: Option<StreamRecorder>,
         message_tx: mpsc::Sender<AppMessage>,
-        continue_session: bool,
-        resume_session: Option<String>,
     ) -> Result<Self> {
-        let mut cmd = Command::new("claude");
+        let mut cmd = Command::new(options.program());
 
-        // Always use print mode with streaming JSON
+        // Print mode with streaming JSON in both directions keeps the
+        // process alive across turns
         cmd.arg("--print");
+        cmd.arg("--input-format");
+        cmd.arg("stream-json");
         cmd.arg("--output-format");
         cmd.arg("stream-json");
         cmd.arg("--verbose");
-        cmd.arg("--dangerously-skip-permissions");
-        cmd.arg("--model");
-        cmd.arg(model);
-
-        // Session handling
-        if let Some(session_id) = resume_session {
-            cmd.arg("--resume");
-            cmd.arg(session_id);
-        } else if continue_session {
-            cmd.arg("--continue");
-        }
+        cmd.arg("--include-partial-messages");
+        options.apply(&mut cmd);
 
         // Set up stdio
         cmd.stdin(Stdio::piped());
         cmd.stdout(Stdio::piped());
         cmd.stderr(Stdio::piped());
+        cmd.kill_on_drop(true);
 
-        let child = cmd.spawn()?;
-
-        Ok(Self {
-            child,
-            message_tx,
-            aborted: false,
-        })
-    }
-
-    /// Send a message to Claude and start streaming the response
-    pub async fn send(&mut self, message: &str) -> Result<()> {
-        // Write message to stdin
-        if let Some(ref mut stdin) = self.child.stdin {
-            stdin.write_all(message.as_bytes()).await?;
-            stdin.write_all(b"\n").await?;
-            stdin.flush().await?;
-        }
-
-        // Take stdin to close it (signals end of input)
-        drop(self.child.stdin.take());
+        let mut child = cmd.spawn().map_err(|e| spawn_error(options, e))?;
+        let stdin = child.stdin.take();
+        let interrupted = Arc::new(AtomicBool::new(false));
 
-        // Spawn task to read stdout
-        let stdout = self.child.stdout.take();
-        let tx = self.message_tx.clone();
-
-        if let Some(stdout) = stdout {
+        // Spawn task to read stdout for the lifetime of the process
+        let stdout_task = child.stdout.take().map(|stdout| {
+            let tx = message_tx.clone();
+            let recorder = recorder.clone();
+            let interrupted = interrupted.clone();
             tokio::spawn(async move {
                 let mut reader = BufReader::new(stdout);
                 let mut parser = StreamParser::new();
@@ -87,10 +84,18 @@ impl ClaudeProcess {
                     match reader.read_line(&mut line).await {
                         Ok(0) => break, // EOF
                         Ok(_) => {
+                            if let Some(recorder) = &recorder {
+                                recorder.record_output(&line);
+                            }
+                            // The app already closed the turn; keep draining
+                            if interrupted.load(Ordering::SeqCst) {
+                                continue;
+                            }
                             match parser.parse_line(&line) {
                                 Ok(events) => {
                                     for event in events {
-                                        if tx.send(AppMessage::ClaudeEvent(event)).await.is_err() {
+                                        let message = AppMessage::ClaudeEvent(id, event);
+                                        if tx.send(message).await.is_err() {
                                             return;
                                         }
                                     }
@@ -101,56 +106,192 @@ impl ClaudeProcess {
                             }
                         }
                         Err(e) => {
-                            let _ = tx.send(AppMessage::ClaudeError(e.to_string())).await;
+                            let _ = tx.send(AppMessage::ClaudeError(id, e.to_string())).await;
                             break;
                         }
                     }
                 }
+            })
+        });
 
-                let _ = tx.send(AppMessage::ClaudeFinished).await;
-            });
-        }
-
-        // Spawn task to read stderr
-        let stderr = self.child.stderr.take();
-        let tx_err = self.message_tx.clone();
-
-        if let Some(stderr) = stderr {
+        // Spawn task to forward stderr, keeping the tail for exit errors
+        let stderr_task = child.stderr.take().map(|stderr| {
+            let tx = message_tx.clone();
             tokio::spawn(async move {
                 let mut reader = BufReader::new(stderr);
                 let mut line = String::new();
+                let mut tail = VecDeque::new();
 
                 loop {
                     line.clear();
                     match reader.read_line(&mut line).await {
                         Ok(0) => break,
                         Ok(_) => {
-                            let trimmed = line.trim();
-                            if !trimmed.is_empty() {
-                                tracing::debug!("Claude stderr: {}", trimmed);
+                            let trimmed = line.trim_end();
+                            if trimmed.is_empty() {
+                                continue;
+                            }
+                            tracing::debug!("Claude stderr: {}", trimmed);
+                            if tail.len() == STDERR_TAIL_LINES {
+                                tail.pop_front();
                             }
+                            tail.push_back(trimmed.to_string());
+                            let _ = tx.send(AppMessage::ClaudeStderr(trimmed.to_string())).await;
                         }
                         Err(_) => break,
                     }
                 }
-            });
+                Vec::from(tail)
+            })
+        });
+
+        // The supervisor owns the child: it reports how the process ended
+        // once all of its output has been delivered
+        let (interrupt_tx, interrupt_rx) = oneshot::channel();
+        tokio::spawn(supervise(
+            id,
+            child,
+            stdout_task,
+            stderr_task,
+            interrupt_rx,
+            interrupted.clone(),
+            message_tx,
+        ));
+
+        Ok(Self {
+            id,
+            stdin,
+            interrupt_tx: Some(interrupt_tx),
+            interrupted,
+            recorder,
+        })
+    }
+
+    /// Id that tags this process's messages
+    pub fn id(&self) -> RequestId {
+        self.id
+    }
+
+    /// Send a user message to the running Claude process
+    pub async fn send(&mut self, message: &str) -> Result<()> {
+        let stdin = self
+            .stdin
+            .as_mut()
+            .ok_or_else(|| anyhow::anyhow!("Claude process stdin is closed"))?;
+
+        let mut line = serde_json::to_string(&InputMessage::text(message))?;
+        line.push('\n');
+        stdin.write_all(line.as_bytes()).await?;
+        stdin.flush().await?;
+
+        if let Some(recorder) = &self.recorder {
+            recorder.record_input(message);
         }
 
         Ok(())
     }
 
-    /// Abort the Claude process
-    pub async fn abort(&mut self) {
-        if !self.aborted {
-            self.aborted = true;
-            let _ = self.child.kill().await;
+    /// Interrupt the current turn: SIGINT first, then a kill if Claude
+    /// hasn't exited after a grace period. The caller is expected to close
+    /// the turn itself; nothing more is reported for this process.
+    pub fn interrupt(&mut self) {
+        self.interrupted.store(true, Ordering::SeqCst);
+        if let Some(interrupt_tx) = self.interrupt_tx.take() {
+            let _ = interrupt_tx.send(());
         }
     }
 }
 
 impl Drop for ClaudeProcess {
     fn drop(&mut self) {
-        // Try to kill the process if still running
-        let _ = self.child.start_kill();
+        // Closing the channel tells the supervisor to kill the process
+        self.interrupt_tx.take();
+    }
+}
+
+/// Wait for the process to exit (interrupting or killing it on request),
+/// then report the outcome: a `ClaudeError` with the stderr tail for a
+/// failed exit, and a final `ClaudeFinished`
+async fn supervise(
+    id: RequestId,
+    mut child: Child,
+    stdout_task: Option<JoinHandle<()>>,
+    stderr_task: Option<JoinHandle<Vec<String>>>,
+    interrupt_rx: oneshot::Receiver<()>,
+    interrupted: Arc<AtomicBool>,
+    tx: mpsc::Sender<AppMessage>,
+) {
+    let status = tokio::select! {
+        status = child.wait() => status.ok(),
+        request = interrupt_rx => {
+            let graceful = request.is_ok() && send_sigint(&child);
+            let exited = if graceful {
+                tokio::time::timeout(INTERRUPT_GRACE, child.wait()).await.ok()
+            } else {
+                None
+            };
+            if exited.is_none() {
+                let _ = child.kill().await;
+            }
+            None
+        }
+    };
+
+    if let Some(task) = stdout_task {
+        let _ = task.await;
+    }
+    if interrupted.load(Ordering::SeqCst) {
+        return;
+    }
+    let stderr_tail = match stderr_task {
+        Some(task) => task.await.unwrap_or_default(),
+        None => Vec::new(),
+    };
+
+    if let Some(status) = status.filter(|status| !status.success()) {
+        let _ = tx.send(AppMessage::ClaudeError(id, exit_message(status, &stderr_tail))).await;
+    }
+    let _ = tx.send(AppMessage::ClaudeFinished(id)).await;
+}
+
+/// Ask the process to stop the way a terminal Ctrl+C would
+#[cfg(unix)]
+fn send_sigint(child: &Child) -> bool {
+    match child.id() {
+        Some(pid) => unsafe { libc::kill(pid as libc::pid_t, libc::SIGINT) == 0 },
+        None => false,
+    }
+}
+
+#[cfg(not(unix))]
+fn send_sigint(_child: &Child) -> bool {
+    false
+}
+
+/// Describe a failed exit, with the last stderr lines
+fn exit_message(status: ExitStatus, stderr_tail: &[String]) -> String {
+    let mut message = match status.code() {
+        Some(code) => format!("Claude CLI exited with code {}", code),
+        None => "Claude CLI was terminated by a signal".to_string(),
+    };
+    if stderr_tail.is_empty() {
+        message.push_str(" (no stderr output; see /diagnostics)");
+    } else {
+        message.push_str(":\n");
+        message.push_str(&stderr_tail.join("\n"));
+    }
+    message
+}
+
+/// Turn a spawn failure into guidance, most often a missing CLI
+fn spawn_error(options: &ClaudeOptions, error: std::io::Error) -> anyhow::Error {
+    if error.kind() == std::io::ErrorKind::NotFound {
+        anyhow::anyhow!(
+            "Claude CLI not found ({}). Install it with `npm install -g @anthropic-ai/claude-code` \
+             or point --claude-bin at it.",
+            options.program().display()
+        )
+    } else {
+        anyhow::anyhow!("Failed to start {}: {}", options.program().display(), error)
     }
 }
@@ -0,0 +1,192 @@
+//! Recording and replaying raw Claude CLI streams
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
+
+use crate::app::{AppMessage, RequestId};
+
+use super::StreamParser;
+
+/// One line of a recorded stream file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum StreamRecord {
+    /// A user message written to the CLI's stdin
+    Input {
+        elapsed_ms: u64,
+        at: chrono::DateTime<chrono::Utc>,
+        text: String,
+    },
+    /// A raw line read from the CLI's stdout
+    Output {
+        elapsed_ms: u64,
+        at: chrono::DateTime<chrono::Utc>,
+        line: String,
+    },
+}
+
+impl StreamRecord {
+    /// Time since the recording started
+    pub fn elapsed_ms(&self) -> u64 {
+        match self {
+            Self::Input { elapsed_ms, .. } | Self::Output { elapsed_ms, .. } => *elapsed_ms,
+        }
+    }
+}
+
+/// Appends stream records to a JSONL file, shared by every process spawned
+/// during the session so one file covers the whole conversation
+#[derive(Debug, Clone)]
+pub struct StreamRecorder {
+    file: Arc<Mutex<File>>,
+    started: Instant,
+}
+
+impl StreamRecorder {
+    /// Create (or truncate) the recording file
+    pub fn create(path: &Path) -> Result<Self> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create stream recording {}", path.display()))?;
+        Ok(Self {
+            file: Arc::new(Mutex::new(file)),
+            started: Instant::now(),
+        })
+    }
+
+    /// Record a user message sent to the CLI
+    pub fn record_input(&self, text: &str) {
+        self.write(StreamRecord::Input {
+            elapsed_ms: self.elapsed_ms(),
+            at: chrono::Utc::now(),
+            text: text.to_string(),
+        });
+    }
+
+    /// Record a raw stdout line
+    pub fn record_output(&self, line: &str) {
+        self.write(StreamRecord::Output {
+            elapsed_ms: self.elapsed_ms(),
+            at: chrono::Utc::now(),
+            line: line.trim_end_matches(['\r', '\n']).to_string(),
+        });
+    }
+
+    fn elapsed_ms(&self) -> u64 {
+        self.started.elapsed().as_millis() as u64
+    }
+
+    fn write(&self, record: StreamRecord) {
+        let Ok(json) = serde_json::to_string(&record) else {
+            return;
+        };
+        let Ok(mut file) = self.file.lock() else {
+            return;
+        };
+        // A failed write should never interrupt the conversation
+        if let Err(e) = writeln!(file, "{}", json).and_then(|_| file.flush()) {
+            tracing::debug!("Failed to record stream line: {}", e);
+        }
+    }
+}
+
+/// Read every record from a recording, skipping lines that don't parse
+pub fn load_recording(path: &Path) -> Result<Vec<StreamRecord>> {
+    let file = File::open(path)
+        .with_context(|| format!("Failed to open stream recording {}", path.display()))?;
+    let mut records = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => records.push(record),
+            Err(e) => tracing::debug!("Skipping bad recording line: {} - {}", e, line),
+        }
+    }
+    Ok(records)
+}
+
+/// Feed a recording to the app at its original speed, as if a Claude
+/// process tagged `id` were producing it
+pub fn spawn_replay(
+    id: RequestId,
+    records: Vec<StreamRecord>,
+    message_tx: mpsc::Sender<AppMessage>,
+) {
+    tokio::spawn(async move {
+        let started = Instant::now();
+        let mut parser = StreamParser::new();
+
+        for record in records {
+            let due = Duration::from_millis(record.elapsed_ms());
+            if let Some(wait) = due.checked_sub(started.elapsed()) {
+                tokio::time::sleep(wait).await;
+            }
+
+            let messages = match record {
+                StreamRecord::Input { text, .. } => vec![AppMessage::ReplayInput(id, text)],
+                StreamRecord::Output { line, .. } => match parser.parse_line(&line) {
+                    Ok(events) => events
+                        .into_iter()
+                        .map(|event| AppMessage::ClaudeEvent(id, event))
+                        .collect(),
+                    Err(e) => {
+                        tracing::debug!("Parse error: {}", e);
+                        Vec::new()
+                    }
+                },
+            };
+            for message in messages {
+                if message_tx.send(message).await.is_err() {
+                    return;
+                }
+            }
+        }
+
+        let _ = message_tx.send(AppMessage::ClaudeFinished(id)).await;
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_load_round_trip() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("stream.jsonl");
+        let recorder = StreamRecorder::create(&path).unwrap();
+        recorder.record_input("hello");
+        recorder.record_output("{\"type\":\"result\",\"subtype\":\"success\"}\n");
+
+        let records = load_recording(&path).unwrap();
+
+        assert_eq!(records.len(), 2);
+        assert!(matches!(&records[0], StreamRecord::Input { text, .. } if text == "hello"));
+        assert!(matches!(
+            &records[1],
+            StreamRecord::Output { line, .. } if line == "{\"type\":\"result\",\"subtype\":\"success\"}"
+        ));
+        assert!(records[0].elapsed_ms() <= records[1].elapsed_ms());
+    }
+
+    #[test]
+    fn test_load_skips_bad_lines() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("stream.jsonl");
+        let good = r#"{"kind":"output","elapsed_ms":5,"at":"2025-01-01T00:00:00Z","line":"{}"}"#;
+        std::fs::write(&path, format!("not json\n\n{}\n", good)).unwrap();
+
+        let records = load_recording(&path).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].elapsed_ms(), 5);
+    }
+}
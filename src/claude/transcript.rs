@@ -0,0 +1,280 @@
+//! Session transcripts the Claude CLI keeps under `~/.claude/projects`
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde_json::Value;
+use std::io::{BufRead, BufReader};
+use std::path::{Path, PathBuf};
+
+use super::parser::StreamParser;
+use super::types::StreamEvent;
+
+/// Sessions listed by the resume picker, most recent first
+pub const MAX_LISTED_SESSIONS: usize = 50;
+
+/// Something shown when rebuilding a conversation from its transcript
+#[derive(Debug, Clone)]
+pub enum TranscriptItem {
+    /// A message the user sent, or a slash command they ran
+    Prompt(String),
+    /// Claude's output, as the stream parser reports it
+    Event(StreamEvent),
+}
+
+/// A past session, as listed by the resume picker
+#[derive(Debug, Clone)]
+pub struct TranscriptSummary {
+    pub session_id: String,
+    pub path: PathBuf,
+    /// When the transcript was last written
+    pub modified: DateTime<Local>,
+    /// First line of the first prompt
+    pub first_prompt: String,
+    /// User and assistant messages
+    pub message_count: usize,
+}
+
+/// Directory holding the CLI's transcripts for `project_dir`. The CLI names
+/// it after the project path with every other character replaced by `-`.
+pub fn project_transcripts_dir(project_dir: &Path) -> Option<PathBuf> {
+    let config_dir = match std::env::var_os("CLAUDE_CONFIG_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => dirs::home_dir()?.join(".claude"),
+    };
+    let name: String = project_dir
+        .to_string_lossy()
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect();
+    Some(config_dir.join("projects").join(name))
+}
+
+/// Sessions with at least one prompt in `dir`, most recent first. This
+/// reads every file it lists, so keep it off the UI task.
+pub fn list_transcripts(dir: &Path) -> Vec<TranscriptSummary> {
+    transcript_files(dir)
+        .into_iter()
+        .filter_map(|(path, modified)| summarize(&path, modified))
+        .take(MAX_LISTED_SESSIONS)
+        .collect()
+}
+
+/// The most recently written transcript in `dir`, which is the session
+/// `--continue` picks up
+pub fn latest_transcript(dir: &Path) -> Option<PathBuf> {
+    transcript_files(dir).into_iter().next().map(|(path, _)| path)
+}
+
+/// Transcript files in `dir` and when they were written, most recent first
+fn transcript_files(dir: &Path) -> Vec<(PathBuf, DateTime<Local>)> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(PathBuf, DateTime<Local>)> = entries
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|path| {
+            let modified = std::fs::metadata(&path).and_then(|m| m.modified()).ok()?;
+            Some((path, DateTime::<Local>::from(modified)))
+        })
+        .collect();
+    files.sort_by_key(|(_, modified)| std::cmp::Reverse(*modified));
+    files
+}
+
+/// Summarize one transcript in a single pass over its lines; `None` if it
+/// has no prompts. User and assistant messages are counted; the CLI writes
+/// one line per content block, so lines from the same assistant message
+/// count once.
+fn summarize(path: &Path, modified: DateTime<Local>) -> Option<TranscriptSummary> {
+    let file = std::fs::File::open(path).ok()?;
+    let mut first_prompt = None;
+    let mut message_count = 0;
+    let mut last_message_id = None;
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(record) = serde_json::from_str::<Value>(&line) else {
+            continue;
+        };
+        if !is_main_conversation(&record) {
+            continue;
+        }
+        match record.get("type").and_then(Value::as_str) {
+            Some("user") => {
+                if let Some(text) = prompt_text(&record) {
+                    first_prompt.get_or_insert_with(|| {
+                        text.lines().next().unwrap_or_default().to_string()
+                    });
+                    message_count += 1;
+                }
+            }
+            Some("assistant") => {
+                let id = record.pointer("/message/id").and_then(Value::as_str);
+                if id.is_none() || id != last_message_id.as_deref() {
+                    message_count += 1;
+                }
+                last_message_id = id.map(str::to_string);
+            }
+            _ => {}
+        }
+    }
+    Some(TranscriptSummary {
+        session_id: path.file_stem()?.to_string_lossy().into_owned(),
+        path: path.to_path_buf(),
+        modified,
+        first_prompt: first_prompt?,
+        message_count,
+    })
+}
+
+/// Read a transcript back as the prompts and events that made it
+pub fn load_transcript(path: &Path) -> Result<Vec<TranscriptItem>> {
+    let content = std::fs::read_to_string(path)
+        .with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(parse_transcript(&content))
+}
+
+fn parse_transcript(content: &str) -> Vec<TranscriptItem> {
+    let mut parser = StreamParser::new();
+    let mut items = Vec::new();
+
+    for line in content.lines() {
+        let Ok(record) = serde_json::from_str::<Value>(line) else {
+            continue;
+        };
+        if !is_main_conversation(&record) {
+            continue;
+        }
+        match record.get("type").and_then(Value::as_str) {
+            Some("user") => {
+                if let Some(text) = prompt_text(&record) {
+                    items.push(TranscriptItem::Prompt(text));
+                }
+                // Tool results; plain-text prompts have nothing more
+                if record.pointer("/message/content").is_some_and(Value::is_array) {
+                    items.extend(parse_events(&mut parser, line));
+                }
+            }
+            Some("assistant") => items.extend(parse_events(&mut parser, line)),
+            _ => {}
+        }
+    }
+    items
+}
+
+/// Content events from a transcript line. Usage was counted in the session
+/// it belongs to.
+fn parse_events(parser: &mut StreamParser, line: &str) -> Vec<TranscriptItem> {
+    parser
+        .parse_line(line)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|event| !matches!(event, StreamEvent::Usage { .. }))
+        .map(TranscriptItem::Event)
+        .collect()
+}
+
+/// Skip subagent messages and the CLI's own notes to itself
+fn is_main_conversation(record: &Value) -> bool {
+    let flag = |name: &str| record.get(name).and_then(Value::as_bool).unwrap_or(false);
+    !flag("isSidechain") && !flag("isMeta") && !flag("isCompactSummary")
+}
+
+/// What the user typed, for a user record that isn't only tool results
+fn prompt_text(record: &Value) -> Option<String> {
+    let text = match record.pointer("/message/content")? {
+        Value::String(text) => text.clone(),
+        Value::Array(blocks) => blocks
+            .iter()
+            .filter(|block| block.get("type").and_then(Value::as_str) == Some("text"))
+            .filter_map(|block| block.get("text").and_then(Value::as_str))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        _ => return None,
+    };
+
+    // Slash commands are recorded as tagged text, and their output follows
+    // as another user record
+    if text.starts_with("<local-command-stdout>") {
+        return None;
+    }
+    if let Some(name) = tag_content(&text, "command-name") {
+        let args = tag_content(&text, "command-args").unwrap_or_default();
+        return Some(format!("{} {}", name, args).trim_end().to_string());
+    }
+    let text = text.trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// Text between `<tag>` and `</tag>`
+fn tag_content<'a>(text: &'a str, tag: &str) -> Option<&'a str> {
+    let start = text.find(&format!("<{}>", tag))? + tag.len() + 2;
+    let end = start + text[start..].find(&format!("</{}>", tag))?;
+    Some(text[start..end].trim())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRANSCRIPT: &str = r#"{"type":"summary","summary":"Fixing tests","leafUuid":"u9"}
+{"type":"user","isMeta":true,"message":{"role":"user","content":"Caveat: generated by local commands"}}
+{"type":"user","message":{"role":"user","content":"Why does the build fail?\nIt worked yesterday."}}
+{"type":"assistant","message":{"id":"msg_1","role":"assistant","content":[{"type":"text","text":"Let me check."}],"usage":{"input_tokens":10,"output_tokens":5}}}
+{"type":"assistant","message":{"id":"msg_1","role":"assistant","content":[{"type":"tool_use","id":"toolu_1","name":"Bash","input":{"command":"cargo build"}}]}}
+{"type":"user","message":{"role":"user","content":[{"type":"tool_result","tool_use_id":"toolu_1","content":"error: missing semicolon"}]}}
+{"type":"assistant","isSidechain":true,"message":{"id":"msg_2","role":"assistant","content":[{"type":"text","text":"subagent"}]}}
+{"type":"assistant","message":{"id":"msg_3","role":"assistant","content":[{"type":"text","text":"A semicolon is missing."}]}}
+{"type":"user","message":{"role":"user","content":"<command-name>/compact</command-name>\n<command-message>compact</command-message>\n<command-args>keep the errors</command-args>"}}
+{"type":"user","message":{"role":"user","content":"<local-command-stdout>Compacted</local-command-stdout>"}}
+"#;
+
+    #[test]
+    fn test_load_transcript() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        let path = dir.join("abc-123.jsonl");
+        std::fs::write(&path, TRANSCRIPT).unwrap();
+
+        let items = load_transcript(&path).unwrap();
+        let described: Vec<String> = items
+            .iter()
+            .map(|item| match item {
+                TranscriptItem::Prompt(text) => format!("prompt: {}", text),
+                TranscriptItem::Event(StreamEvent::Text(text)) => format!("text: {}", text),
+                TranscriptItem::Event(StreamEvent::ToolUse { name, .. }) => {
+                    format!("tool: {}", name)
+                }
+                TranscriptItem::Event(StreamEvent::ToolResult { name, result, .. }) => {
+                    format!("result: {} {}", name, result)
+                }
+                TranscriptItem::Event(other) => format!("other: {:?}", other),
+            })
+            .collect();
+        assert_eq!(
+            described,
+            [
+                "prompt: Why does the build fail?\nIt worked yesterday.",
+                "text: Let me check.",
+                "tool: Bash",
+                "result: Bash error: missing semicolon",
+                "text: A semicolon is missing.",
+                "prompt: /compact keep the errors",
+            ]
+        );
+
+        let sessions = list_transcripts(dir);
+        assert_eq!(latest_transcript(dir), Some(path));
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].session_id, "abc-123");
+        assert_eq!(sessions[0].first_prompt, "Why does the build fail?");
+        assert_eq!(sessions[0].message_count, 4);
+    }
+
+    #[test]
+    fn test_project_transcripts_dir_name() {
+        let dir = project_transcripts_dir(Path::new("/home/me/my_app.rs")).unwrap();
+        assert_eq!(dir.file_name().unwrap(), "-home-me-my-app-rs");
+        assert_eq!(dir.parent().unwrap().file_name().unwrap(), "projects");
+    }
+}
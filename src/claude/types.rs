@@ -8,9 +8,16 @@ pub enum StreamEvent {
     /// Text content (delta or full)
     Text(String),
     /// Tool use started
-    ToolUse { name: String, input: String },
-    /// Tool result received
-    ToolResult { name: String, result: String },
+    ToolUse { id: String, name: String, input: String },
+    /// Tool input received so far (partial JSON until the block stops)
+    ToolInput { id: String, input: String },
+    /// Tool result received for the tool use with the same id
+    ToolResult {
+        id: String,
+        name: String,
+        result: String,
+        is_error: bool,
+    },
     /// Thinking content
     Thinking(String),
     /// Token usage update
@@ -20,6 +27,93 @@ pub enum StreamEvent {
         cache_read_tokens: u64,
         cache_write_tokens: u64,
     },
+    /// Session details from the system init event
+    SessionInit(SessionInit),
+    /// The CLI compacted the conversation; `pre_tokens` is the context size
+    /// before compaction, when reported
+    Compacted { trigger: String, pre_tokens: Option<u64> },
+    /// Event produced by a subagent running inside a Task tool call
+    Subagent {
+        parent_tool_use_id: String,
+        event: Box<StreamEvent>,
+    },
+    /// The CLI finished responding to the current user message
+    TurnComplete {
+        cost_usd: f64,
+        duration_ms: u64,
+        duration_api_ms: u64,
+        num_turns: u32,
+        is_error: bool,
+    },
+}
+
+/// Details of a compact_boundary system event
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct CompactMetadata {
+    /// `manual` for /compact, `auto` when the CLI did it itself
+    #[serde(default)]
+    pub trigger: String,
+    #[serde(default)]
+    pub pre_tokens: Option<u64>,
+}
+
+/// Session details reported by the CLI's system init event
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct SessionInit {
+    #[serde(default)]
+    pub session_id: String,
+    #[serde(default)]
+    pub model: String,
+    #[serde(default)]
+    pub cwd: String,
+    #[serde(default)]
+    pub tools: Vec<String>,
+    #[serde(default)]
+    pub mcp_servers: Vec<McpServerInfo>,
+}
+
+/// MCP server entry in the system init event
+#[derive(Debug, Clone, Deserialize)]
+pub struct McpServerInfo {
+    pub name: String,
+    #[serde(default)]
+    pub status: String,
+}
+
+/// User message written to Claude CLI stdin in stream-json input mode
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type", rename = "user")]
+pub struct InputMessage {
+    pub message: InputMessageBody,
+}
+
+/// Body of a user input message
+#[derive(Debug, Clone, Serialize)]
+pub struct InputMessageBody {
+    pub role: &'static str,
+    pub content: Vec<InputContent>,
+}
+
+/// Content block in a user input message
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "type")]
+pub enum InputContent {
+    #[serde(rename = "text")]
+    Text { text: String },
+}
+
+impl InputMessage {
+    /// Build a plain text user message
+    pub fn text(text: &str) -> Self {
+        Self {
+            message: InputMessageBody {
+                role: "user",
+                content: vec![InputContent::Text {
+                    text: text.to_string(),
+                }],
+            },
+        }
+    }
 }
 
 /// Message role in conversation
@@ -48,7 +142,10 @@ pub enum ContentBlock {
     #[serde(rename = "tool_result")]
     ToolResult {
         tool_use_id: String,
+        #[serde(default)]
         content: serde_json::Value,
+        #[serde(default)]
+        is_error: bool,
     },
     #[serde(rename = "thinking")]
     Thinking { thinking: String },
@@ -80,9 +177,15 @@ pub enum RawStreamEvent {
         data: serde_json::Value,
     },
     #[serde(rename = "assistant")]
-    Assistant { message: ClaudeMessage },
+    Assistant {
+        message: ClaudeMessage,
+        parent_tool_use_id: Option<String>,
+    },
     #[serde(rename = "user")]
-    User { message: ClaudeMessage },
+    User {
+        message: ClaudeMessage,
+        parent_tool_use_id: Option<String>,
+    },
     #[serde(rename = "content_block_start")]
     ContentBlockStart {
         index: u64,
@@ -104,11 +207,21 @@ pub enum RawStreamEvent {
     },
     #[serde(rename = "message_stop")]
     MessageStop,
+    /// Partial message event wrapped by `--include-partial-messages`
+    #[serde(rename = "stream_event")]
+    StreamEvent {
+        event: Box<RawStreamEvent>,
+        parent_tool_use_id: Option<String>,
+    },
     #[serde(rename = "result")]
     Result {
         subtype: Option<String>,
         result: Option<serde_json::Value>,
         is_error: Option<bool>,
+        total_cost_usd: Option<f64>,
+        duration_ms: Option<u64>,
+        duration_api_ms: Option<u64>,
+        num_turns: Option<u32>,
         #[serde(flatten)]
         data: serde_json::Value,
     },
@@ -0,0 +1,175 @@
+//! User and project configuration files
+
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::budget::Budget;
+
+/// Project config file, relative to the project directory
+pub const PROJECT_CONFIG_FILE: &str = ".claude-terminal/config.json";
+
+/// Settings from `<config dir>/claude-terminal/config.json` and the
+/// project's `.claude-terminal/config.json`. Project values take precedence;
+/// lists are combined.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// MCP config files (or inline JSON) passed to Claude with `--mcp-config`.
+    /// Relative paths are resolved against the directory the setting is for:
+    /// the project root, or the user config directory.
+    pub mcp_config: Vec<String>,
+    /// Use only the servers from `mcp_config`, ignoring other MCP settings
+    pub strict_mcp_config: Option<bool>,
+    /// Fraction of the context window (0-1) at which to offer /compact
+    pub compact_at: Option<f64>,
+    /// Number the lines of code blocks and files
+    pub line_numbers: Option<bool>,
+    /// Limits for each session
+    pub budget: Budget,
+    /// Limits for the project, across sessions
+    pub project_budget: Budget,
+}
+
+impl Config {
+    /// Load and merge the user and project config files. Missing files are
+    /// fine; files that don't parse are an error.
+    pub fn load(project_dir: &Path) -> Result<Self> {
+        let mut config = Self::default();
+        if let Some(dir) = dirs::config_dir().map(|dir| dir.join("claude-terminal")) {
+            if let Some(user) = Self::read(&dir.join("config.json"), &dir)? {
+                config.merge(user);
+            }
+        }
+        if let Some(project) = Self::read(&project_dir.join(PROJECT_CONFIG_FILE), project_dir)? {
+            config.merge(project);
+        }
+        Ok(config)
+    }
+
+    fn read(path: &Path, base: &Path) -> Result<Option<Self>> {
+        let content = match std::fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e).with_context(|| format!("Failed to read {}", path.display())),
+        };
+        let mut config: Self = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        config
+            .check()
+            .with_context(|| format!("Invalid config file {}", path.display()))?;
+        config.mcp_config = config
+            .mcp_config
+            .into_iter()
+            .map(|entry| resolve_mcp_config(base, entry))
+            .collect();
+        Ok(Some(config))
+    }
+
+    /// Reject values that parse but make no sense
+    fn check(&self) -> Result<()> {
+        if let Some(fraction) = self.compact_at {
+            if !(0.0..=1.0).contains(&fraction) {
+                bail!(
+                    "compact_at is a fraction of the context window from 0 to 1 (e.g. 0.8), \
+                     not {}",
+                    fraction
+                );
+            }
+        }
+        self.budget.validate().context("in budget")?;
+        self.project_budget.validate().context("in project_budget")?;
+        Ok(())
+    }
+
+    fn merge(&mut self, other: Self) {
+        self.mcp_config.extend(other.mcp_config);
+        self.strict_mcp_config = other.strict_mcp_config.or(self.strict_mcp_config);
+        self.compact_at = other.compact_at.or(self.compact_at);
+        self.line_numbers = other.line_numbers.or(self.line_numbers);
+        self.budget.merge(other.budget);
+        self.project_budget.merge(other.project_budget);
+    }
+}
+
+/// Make a relative MCP config path absolute; inline JSON is left alone
+pub fn resolve_mcp_config(base: &Path, entry: String) -> String {
+    if entry.trim_start().starts_with('{') || Path::new(&entry).is_absolute() {
+        return entry;
+    }
+    base.join(&entry).to_string_lossy().into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_project_config_resolves_paths() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+        std::fs::create_dir_all(dir.join(".claude-terminal")).unwrap();
+        std::fs::write(
+            dir.join(PROJECT_CONFIG_FILE),
+            r#"{"mcp_config": ["mcp.json", "/etc/mcp.json", "{\"mcpServers\":{}}"], "strict_mcp_config": true}"#,
+        )
+        .unwrap();
+
+        let config = Config::read(&dir.join(PROJECT_CONFIG_FILE), dir).unwrap().unwrap();
+
+        assert_eq!(config.mcp_config[0], dir.join("mcp.json").to_string_lossy());
+        assert_eq!(config.mcp_config[1], "/etc/mcp.json");
+        assert_eq!(config.mcp_config[2], r#"{"mcpServers":{}}"#);
+        assert_eq!(config.strict_mcp_config, Some(true));
+    }
+
+    #[test]
+    fn test_merge_prefers_project_values() {
+        let mut config = Config {
+            mcp_config: vec!["user.json".to_string()],
+            strict_mcp_config: Some(true),
+            budget: Budget {
+                max_cost_usd: Some(5.0),
+                max_turns: Some(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        config.merge(Config {
+            mcp_config: vec!["project.json".to_string()],
+            strict_mcp_config: Some(false),
+            budget: Budget {
+                max_cost_usd: Some(1.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        });
+        assert_eq!(config.mcp_config, vec!["user.json", "project.json"]);
+        assert_eq!(config.strict_mcp_config, Some(false));
+        assert_eq!(config.budget.max_cost_usd, Some(1.0));
+        assert_eq!(config.budget.max_turns, Some(10));
+
+        config.merge(Config::default());
+        assert_eq!(config.strict_mcp_config, Some(false));
+    }
+
+    #[test]
+    fn test_invalid_config_is_an_error() {
+        let temp = tempfile::tempdir().unwrap();
+        let dir = temp.path();
+
+        let read_error = |content: &str| {
+            std::fs::write(dir.join("config.json"), content).unwrap();
+            let result = Config::read(&dir.join("config.json"), dir);
+            result.map(|_| ()).map_err(|e| format!("{:#}", e)).unwrap_err()
+        };
+        let errors = [
+            read_error("{ not json"),
+            read_error(r#"{"compact_at": -0.5}"#),
+            read_error(r#"{"project_budget": {"warn_at": 80}}"#),
+        ];
+        assert!(errors[0].starts_with("Invalid config file"));
+        assert!(errors[1].contains("compact_at is a fraction"), "{}", errors[1]);
+        assert!(errors[2].contains("in project_budget: warn_at is a fraction"), "{}", errors[2]);
+    }
+}
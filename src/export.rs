@@ -0,0 +1,470 @@
+//! Export the conversation as Markdown, HTML or JSON
+
+use ratatui::style::{Color, Style};
+use serde_json::{json, Value};
+
+use crate::app::{ConversationContent, ConversationEntry, Role, ToolStatus};
+use crate::ui::{self, display_tool_name};
+
+/// File format for `/export`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Markdown,
+    Html,
+    Json,
+}
+
+impl ExportFormat {
+    /// Parse a format name or file extension
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "md" | "markdown" => Some(Self::Markdown),
+            "html" | "htm" => Some(Self::Html),
+            "json" => Some(Self::Json),
+            _ => None,
+        }
+    }
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Self::Markdown => "md",
+            Self::Html => "html",
+            Self::Json => "json",
+        }
+    }
+}
+
+/// Render `entries` in `format`. Thinking is left out unless
+/// `include_thinking` is set.
+pub fn export_conversation(
+    entries: &[ConversationEntry],
+    format: ExportFormat,
+    include_thinking: bool,
+) -> String {
+    let entries: Vec<&ConversationEntry> = entries
+        .iter()
+        .filter(|entry| include_thinking || !is_thinking(entry))
+        .collect();
+    match format {
+        ExportFormat::Markdown => {
+            let mut out = format!("# Conversation\n\nExported {}\n\n", exported_at());
+            for entry in entries {
+                push_markdown(&mut out, entry, include_thinking);
+            }
+            out
+        }
+        ExportFormat::Html => export_html(&entries, include_thinking),
+        ExportFormat::Json => {
+            let entries: Vec<Value> =
+                entries.iter().map(|entry| entry_json(entry, include_thinking)).collect();
+            let document = json!({ "exported_at": exported_at(), "entries": entries });
+            serde_json::to_string_pretty(&document).unwrap_or_default()
+        }
+    }
+}
+
+fn exported_at() -> String {
+    chrono::Local::now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn is_thinking(entry: &ConversationEntry) -> bool {
+    matches!(entry.content, ConversationContent::Thinking(_))
+}
+
+fn role_name(role: &Role) -> &'static str {
+    match role {
+        Role::User => "user",
+        Role::Assistant => "assistant",
+        Role::System => "system",
+        Role::Tool => "tool",
+        Role::Bash => "bash",
+    }
+}
+
+/// Status words as the conversation view shows them
+fn status_name(status: ToolStatus) -> &'static str {
+    match status {
+        ToolStatus::Pending => "running",
+        ToolStatus::Success => "done",
+        ToolStatus::Error => "failed",
+        ToolStatus::Interrupted => "interrupted",
+    }
+}
+
+fn push_markdown(out: &mut String, entry: &ConversationEntry, include_thinking: bool) {
+    match &entry.content {
+        ConversationContent::Text(text) => match entry.role {
+            Role::User => out.push_str(&format!("## You\n\n{}\n\n", text)),
+            Role::Assistant => out.push_str(&format!("## Claude\n\n{}\n\n", text)),
+            _ => out.push_str(&format!("{}\n\n", quote(text))),
+        },
+        ConversationContent::ToolUse {
+            name,
+            input,
+            status,
+            result,
+            ..
+        } => {
+            out.push_str(&format!(
+                "**Tool: {}** ({})\n\n{}\n",
+                display_tool_name(name),
+                status_name(*status),
+                code_block(input, "json")
+            ));
+            if let Some(result) = result {
+                out.push_str(&format!(
+                    "<details><summary>Result</summary>\n\n{}\n</details>\n\n",
+                    code_block(result, "")
+                ));
+            }
+        }
+        ConversationContent::ToolResult { name, result } => {
+            out.push_str(&format!("**{} result**\n\n{}\n", name, code_block(result, "")));
+        }
+        ConversationContent::Thinking(text) => {
+            out.push_str(&format!(
+                "<details><summary>Thinking</summary>\n\n{}\n\n</details>\n\n",
+                text
+            ));
+        }
+        ConversationContent::BashCommand {
+            command,
+            output,
+            exit_code,
+            ..
+        } => {
+            let exit = match exit_code {
+                Some(code) => format!("exit code {}", code),
+                None => "running".to_string(),
+            };
+            let session = format!("$ {}\n{}", command, output);
+            out.push_str(&format!("**Bash** ({})\n\n{}\n", exit, code_block(&session, "console")));
+        }
+    }
+
+    // Subagent work, quoted under its Task call
+    let children: Vec<&ConversationEntry> = entry
+        .children
+        .iter()
+        .filter(|child| include_thinking || !is_thinking(child))
+        .collect();
+    if !children.is_empty() {
+        let mut nested = String::from("**Subagent**\n\n");
+        for child in children {
+            push_markdown(&mut nested, child, include_thinking);
+        }
+        out.push_str(&quote(nested.trim_end()));
+        out.push_str("\n\n");
+    }
+}
+
+/// Prefix every line with `> `
+fn quote(text: &str) -> String {
+    text.lines()
+        .map(|line| if line.is_empty() { ">".to_string() } else { format!("> {}", line) })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Fenced code block, with a fence longer than any backtick run inside
+fn code_block(text: &str, language: &str) -> String {
+    let mut longest = 0;
+    let mut run = 0;
+    for c in text.chars() {
+        run = if c == '`' { run + 1 } else { 0 };
+        longest = longest.max(run);
+    }
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}\n", fence, language, text.trim_end_matches('\n'), fence)
+}
+
+fn entry_json(entry: &ConversationEntry, include_thinking: bool) -> Value {
+    let mut value = match &entry.content {
+        ConversationContent::Text(text) => json!({ "type": "text", "text": text }),
+        ConversationContent::ToolUse {
+            name,
+            input,
+            status,
+            result,
+            ..
+        } => json!({
+            "type": "tool_use",
+            "name": name,
+            // Structured when it parses, as the CLI sent it otherwise
+            "input": serde_json::from_str::<Value>(input).unwrap_or_else(|_| json!(input)),
+            "status": status_name(*status),
+            "result": result,
+        }),
+        ConversationContent::ToolResult { name, result } => {
+            json!({ "type": "tool_result", "name": name, "result": result })
+        }
+        ConversationContent::Thinking(text) => json!({ "type": "thinking", "text": text }),
+        ConversationContent::BashCommand {
+            command,
+            output,
+            exit_code,
+            ..
+        } => json!({
+            "type": "bash",
+            "command": command,
+            "output": output,
+            "exit_code": exit_code,
+        }),
+    };
+    value["role"] = json!(role_name(&entry.role));
+    value["timestamp"] = json!(entry.timestamp.to_rfc3339());
+    let children: Vec<Value> = entry
+        .children
+        .iter()
+        .filter(|child| include_thinking || !is_thinking(child))
+        .map(|child| entry_json(child, include_thinking))
+        .collect();
+    if !children.is_empty() {
+        value["subagent"] = json!(children);
+    }
+    value
+}
+
+/// CSS color for a palette color
+fn css(color: Color) -> String {
+    match color {
+        Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+        _ => "inherit".to_string(),
+    }
+}
+
+/// CSS color of a TUI style, so each role looks as it does on screen
+fn style_css(style: Style) -> String {
+    css(style.fg.unwrap_or(ui::TEXT))
+}
+
+fn export_html(entries: &[&ConversationEntry], include_thinking: bool) -> String {
+    let mut body = String::new();
+    for entry in entries {
+        push_html(&mut body, entry, include_thinking);
+    }
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>Conversation</title>
+<style>
+body {{
+  background: {base}; color: {text}; font-family: ui-sans-serif, system-ui, sans-serif;
+  max-width: 900px; margin: 2em auto; line-height: 1.5;
+}}
+pre {{
+  background: {mantle}; border: 1px solid {surface0}; border-radius: 6px;
+  padding: 0.6em 0.8em; white-space: pre-wrap; word-wrap: break-word;
+}}
+.entry {{ margin: 1.2em 0; }}
+.role {{ font-weight: bold; }}
+.user .role {{ color: {user}; }}
+.assistant .role {{ color: {assistant}; }}
+.system {{ color: {system}; font-style: italic; }}
+.tool summary {{ color: {tool}; cursor: pointer; }}
+.result {{ color: {result}; }}
+.failed {{ color: {error}; }}
+.bash .command {{ color: {bash}; font-weight: bold; }}
+.thinking {{ color: {thinking}; font-style: italic; }}
+.subagent {{ border-left: 2px solid {surface2}; padding-left: 1em; margin-left: 0.5em; }}
+.meta {{ color: {subtext}; }}
+</style>
+</head>
+<body>
+<h1>Conversation</h1>
+<p class="meta">Exported {exported}</p>
+{body}</body>
+</html>
+"#,
+        base = css(ui::BASE),
+        text = css(ui::TEXT),
+        mantle = css(ui::MANTLE),
+        surface0 = css(ui::SURFACE0),
+        surface2 = css(ui::SURFACE2),
+        subtext = css(ui::SUBTEXT0),
+        user = style_css(ui::user_style()),
+        assistant = style_css(ui::assistant_style()),
+        system = style_css(ui::system_style()),
+        tool = style_css(ui::tool_style()),
+        result = style_css(ui::tool_result_style()),
+        error = style_css(ui::error_style()),
+        bash = style_css(ui::bash_style()),
+        thinking = style_css(ui::thinking_style()),
+        exported = exported_at(),
+        body = body,
+    )
+}
+
+fn push_html(out: &mut String, entry: &ConversationEntry, include_thinking: bool) {
+    let role = role_name(&entry.role);
+    match &entry.content {
+        ConversationContent::Text(text) => {
+            let label = match entry.role {
+                Role::User => "You",
+                Role::Assistant => "Claude",
+                _ => "",
+            };
+            if label.is_empty() {
+                out.push_str(&format!(
+                    "<div class=\"entry {}\"><pre>{}</pre></div>\n",
+                    role,
+                    escape(text)
+                ));
+            } else {
+                out.push_str(&format!(
+                    "<div class=\"entry {}\"><div class=\"role\">{}</div><pre>{}</pre></div>\n",
+                    role,
+                    label,
+                    escape(text)
+                ));
+            }
+        }
+        ConversationContent::ToolUse {
+            name,
+            input,
+            status,
+            result,
+            ..
+        } => {
+            let class = if *status == ToolStatus::Error { "result failed" } else { "result" };
+            let result = result
+                .as_ref()
+                .map(|result| format!("<pre class=\"{}\">{}</pre>", class, escape(result)))
+                .unwrap_or_default();
+            out.push_str(&format!(
+                "<details class=\"entry tool\"><summary>{} ({})</summary><pre>{}</pre>{}\
+                 </details>\n",
+                escape(&display_tool_name(name)),
+                status_name(*status),
+                escape(input),
+                result
+            ));
+        }
+        ConversationContent::ToolResult { name, result } => {
+            out.push_str(&format!(
+                "<details class=\"entry tool\"><summary>{} result</summary>\
+                 <pre class=\"result\">{}</pre></details>\n",
+                escape(name),
+                escape(result)
+            ));
+        }
+        ConversationContent::Thinking(text) => {
+            out.push_str(&format!(
+                "<details class=\"entry thinking\"><summary>Thinking</summary><pre>{}</pre>\
+                 </details>\n",
+                escape(text)
+            ));
+        }
+        ConversationContent::BashCommand {
+            command,
+            output,
+            exit_code,
+            ..
+        } => {
+            let exit = match exit_code {
+                Some(0) => "<span class=\"meta\">exit code 0</span>".to_string(),
+                Some(code) => format!("<span class=\"failed\">exit code {}</span>", code),
+                None => "<span class=\"meta\">running</span>".to_string(),
+            };
+            out.push_str(&format!(
+                "<div class=\"entry bash\"><pre><span class=\"command\">$ {}</span>\n{}</pre>{}\
+                 </div>\n",
+                escape(command),
+                escape(output),
+                exit
+            ));
+        }
+    }
+
+    let children: Vec<&ConversationEntry> = entry
+        .children
+        .iter()
+        .filter(|child| include_thinking || !is_thinking(child))
+        .collect();
+    if !children.is_empty() {
+        out.push_str("<div class=\"subagent\">\n");
+        for child in children {
+            push_html(out, child, include_thinking);
+        }
+        out.push_str("</div>\n");
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn conversation() -> Vec<ConversationEntry> {
+        let text = |role, text: &str| {
+            ConversationEntry::new(role, ConversationContent::Text(text.to_string()))
+        };
+        vec![
+            text(Role::User, "Why is <main> failing?"),
+            ConversationEntry::new(Role::Assistant, ConversationContent::Thinking("hmm".into())),
+            ConversationEntry::new(
+                Role::Tool,
+                ConversationContent::ToolUse {
+                    id: "toolu_1".to_string(),
+                    name: "mcp__github__get_run".to_string(),
+                    input: "{\"run\": 12}".to_string(),
+                    status: ToolStatus::Error,
+                    result: Some("``` not found".to_string()),
+                    finished_at: None,
+                },
+            ),
+            ConversationEntry::new(
+                Role::Bash,
+                ConversationContent::BashCommand {
+                    id: crate::app::RequestId(1),
+                    command: "cargo test".to_string(),
+                    output: "1 failed\n".to_string(),
+                    exit_code: Some(101),
+                },
+            ),
+            text(Role::Assistant, "A test fails."),
+        ]
+    }
+
+    #[test]
+    fn test_markdown_export() {
+        let markdown = export_conversation(&conversation(), ExportFormat::Markdown, false);
+        assert!(markdown.contains("## You\n\nWhy is <main> failing?\n\n"));
+        assert!(markdown.contains("**Tool: github/get_run** (failed)"));
+        // The fence outgrows backticks in the result
+        assert!(markdown.contains("````\n``` not found\n````"));
+        let bash = "**Bash** (exit code 101)\n\n```console\n$ cargo test\n1 failed\n```";
+        assert!(markdown.contains(bash));
+        assert!(markdown.contains("## Claude\n\nA test fails."));
+        assert!(!markdown.contains("Thinking"));
+
+        let markdown = export_conversation(&conversation(), ExportFormat::Markdown, true);
+        assert!(markdown.contains("<summary>Thinking</summary>\n\nhmm"));
+    }
+
+    #[test]
+    fn test_html_and_json_export() {
+        let html = export_conversation(&conversation(), ExportFormat::Html, false);
+        assert!(html.contains("Why is &lt;main&gt; failing?"));
+        assert!(html.contains(&format!("background: {}", css(ui::BASE))));
+        assert!(html.contains("<span class=\"failed\">exit code 101</span>"));
+
+        let json: Value =
+            serde_json::from_str(&export_conversation(&conversation(), ExportFormat::Json, true))
+                .unwrap();
+        let entries = json["entries"].as_array().unwrap();
+        assert_eq!(entries.len(), 5);
+        assert_eq!(entries[0]["role"], "user");
+        assert_eq!(entries[2]["input"]["run"], 12);
+        assert_eq!(entries[2]["status"], "failed");
+        assert_eq!(entries[3]["exit_code"], 101);
+    }
+}
@@ -56,6 +56,25 @@ pub fn delete_to_start(input: &str, cursor_position: usize) -> String {
     input[cursor_position..].to_string()
 }
 
+/// Longest prefix shared by all `words`, for Tab completion
+pub fn common_prefix<'a>(words: &[&'a str]) -> &'a str {
+    let Some(first) = words.first() else {
+        return "";
+    };
+    let mut len = first.len();
+    for word in &words[1..] {
+        len = first
+            .char_indices()
+            .zip(word.chars())
+            .take_while(|((_, a), b)| a == b)
+            .map(|((i, a), _)| i + a.len_utf8())
+            .last()
+            .unwrap_or(0)
+            .min(len);
+    }
+    &first[..len]
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -149,4 +168,13 @@ mod tests {
         assert_eq!(find_word_boundary_backward(input, 11), 0); // "hello-world" is one word
         assert_eq!(find_word_boundary_forward(input, 0), 12);
     }
+
+    #[test]
+    fn test_common_prefix() {
+        assert_eq!(common_prefix(&["permissions", "pr-comments"]), "p");
+        assert_eq!(common_prefix(&["session", "sessions", "send"]), "se");
+        assert_eq!(common_prefix(&["model"]), "model");
+        assert_eq!(common_prefix(&["cd", "quit"]), "");
+        assert_eq!(common_prefix(&[]), "");
+    }
 }
@@ -2,14 +2,23 @@
 
 mod app;
 mod bash;
+mod budget;
 mod claude;
+mod config;
+mod export;
 mod input_utils;
+mod permissions;
 mod sessions;
+mod store;
 mod ui;
 mod voice;
 
+#[cfg(test)]
+mod app_tests;
+
 use anyhow::Result;
 use clap::Parser;
+use std::path::PathBuf;
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
 #[derive(Parser, Debug)]
@@ -17,14 +26,28 @@ use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 #[command(about = "A fast, responsive terminal interface for Claude Code.")]
 #[command(version)]
 struct Args {
-    /// Model to use (e.g., opus, sonnet, haiku)
-    #[arg(short, long, default_value = "opus")]
+    /// Model to use (e.g., sonnet, opus, haiku, or a full model ID)
+    #[arg(
+        short,
+        long,
+        env = "CLAUDE_TERMINAL_MODEL",
+        default_value = "sonnet",
+        value_parser = parse_model
+    )]
     model: String,
 
+    /// Model to switch to when the main one is overloaded
+    #[arg(long, value_name = "MODEL", value_parser = parse_model)]
+    fallback_model: Option<String>,
+
     /// Working directory
     #[arg(short = 'd', long)]
     directory: Option<String>,
 
+    /// Another directory Claude may access (repeatable)
+    #[arg(long = "add-dir", value_name = "DIR")]
+    add_dirs: Vec<PathBuf>,
+
     /// Continue the most recent conversation
     #[arg(short, long)]
     continue_session: bool,
@@ -33,15 +56,79 @@ struct Args {
     #[arg(short, long)]
     resume: Option<String>,
 
+    /// Permission mode (default, plan, acceptEdits, bypassPermissions)
+    #[arg(long, value_enum, default_value_t = claude::PermissionMode::Default)]
+    permission_mode: claude::PermissionMode,
+
+    /// Tools that run without asking (comma-separated, e.g. "Read,Bash(git log:*)")
+    #[arg(long, value_delimiter = ',')]
+    allowed_tools: Vec<String>,
+
+    /// Tools that are never allowed (comma-separated)
+    #[arg(long, value_delimiter = ',')]
+    disallowed_tools: Vec<String>,
+
+    /// Replace Claude Code's default system prompt
+    #[arg(long, value_name = "PROMPT")]
+    system_prompt: Option<String>,
+
+    /// Add instructions to the end of the system prompt (after the
+    /// project's .claude-terminal/prompt.md)
+    #[arg(long, value_name = "PROMPT")]
+    append_system_prompt: Option<String>,
+
+    /// MCP config file or JSON string to load servers from (repeatable;
+    /// added to any from the config files)
+    #[arg(long = "mcp-config", value_name = "FILE")]
+    mcp_configs: Vec<String>,
+
+    /// Only use MCP servers from --mcp-config and the config files
+    #[arg(long)]
+    strict_mcp_config: bool,
+
+    /// Stop the session after this many tokens (e.g. 500k)
+    #[arg(long, value_name = "TOKENS", value_parser = parse_token_count)]
+    max_tokens: Option<u64>,
+
+    /// Stop the session after this much spending, in USD
+    #[arg(long, value_name = "USD", value_parser = parse_cost)]
+    max_cost: Option<f64>,
+
+    /// Agentic turns Claude may take per message
+    #[arg(long, value_name = "N")]
+    max_turns: Option<u32>,
+
+    /// Record the raw Claude stream to a JSONL file
+    #[arg(long, value_name = "FILE")]
+    record_stream: Option<PathBuf>,
+
+    /// Replay a recorded stream instead of running Claude
+    #[arg(long, value_name = "FILE", conflicts_with = "record_stream")]
+    replay: Option<PathBuf>,
+
+    /// Path to the Claude CLI executable
+    #[arg(long, value_name = "PATH", env = "CLAUDE_TERMINAL_CLAUDE_BIN")]
+    claude_bin: Option<PathBuf>,
+
     /// Enable debug logging
     #[arg(long)]
     debug: bool,
+
+    /// Run as the permission prompt MCP server (spawned by the Claude CLI)
+    #[arg(long, hide = true)]
+    permission_bridge: Option<String>,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     let args = Args::parse();
 
+    // The Claude CLI launches us as its permission prompt server; stdout
+    // belongs to the MCP protocol, so skip all TUI setup
+    if let Some(addr) = &args.permission_bridge {
+        return permissions::run_bridge(addr).await;
+    }
+
     // Set up logging
     let filter = if args.debug {
         EnvFilter::new("debug")
@@ -54,12 +141,94 @@ async fn main() -> Result<()> {
         .with(filter)
         .init();
 
+    if args.fallback_model.as_deref() == Some(args.model.as_str()) {
+        anyhow::bail!("--fallback-model must differ from --model ({})", args.model);
+    }
+
     // Change to specified directory if provided
     if let Some(dir) = &args.directory {
         std::env::set_current_dir(dir)?;
     }
 
+    // Open recordings before the terminal switches to raw mode so errors
+    // print normally
+    let recorder = args
+        .record_stream
+        .as_deref()
+        .map(claude::StreamRecorder::create)
+        .transpose()?;
+    let replay = args.replay.as_deref().map(claude::load_recording).transpose()?;
+
+    let cwd = std::env::current_dir()?;
+    let file_config = config::Config::load(&cwd)?;
+    let mut mcp_configs = file_config.mcp_config;
+    mcp_configs.extend(
+        args.mcp_configs
+            .into_iter()
+            .map(|entry| config::resolve_mcp_config(&cwd, entry)),
+    );
+
     // Run the app
-    let mut app = app::App::new(args.model, args.continue_session, args.resume)?;
-    app.run().await
+    let options = claude::ClaudeOptions {
+        claude_bin: args.claude_bin,
+        model: args.model,
+        fallback_model: args.fallback_model,
+        // Absolute, so they still work after /cd
+        add_dirs: args.add_dirs.iter().map(|dir| cwd.join(dir)).collect(),
+        continue_session: args.continue_session,
+        resume_session: args.resume,
+        permission_mode: args.permission_mode,
+        allowed_tools: args.allowed_tools,
+        disallowed_tools: args.disallowed_tools,
+        system_prompt: args.system_prompt,
+        append_system_prompt: args.append_system_prompt,
+        mcp_configs,
+        strict_mcp_config: args.strict_mcp_config || file_config.strict_mcp_config.unwrap_or(false),
+        ..Default::default()
+    };
+    let mut session_budget = file_config.budget;
+    session_budget.merge(budget::Budget {
+        max_tokens: args.max_tokens,
+        max_cost_usd: args.max_cost,
+        warn_at: None,
+        max_turns: args.max_turns,
+    });
+
+    let mut app = app::App::new(options)?;
+    app.set_budgets(session_budget, file_config.project_budget);
+    app.set_compact_at(file_config.compact_at.unwrap_or(app::DEFAULT_COMPACT_AT));
+    app.set_line_numbers(file_config.line_numbers.unwrap_or(false));
+    if let Some(recorder) = recorder {
+        app.record_stream(recorder);
+    }
+    if let (Some(records), Some(path)) = (replay, &args.replay) {
+        app.replay(records, &path.display().to_string());
+    } else if let Some(root) = store::state_root() {
+        // Keep the conversation on disk, and offer back what a crash left
+        if let Some(saved) = store::SavedState::find(&root, &cwd) {
+            app.offer_restore(saved);
+        }
+        match store::StateStore::create(&root) {
+            Ok(state_store) => app.set_state_store(state_store),
+            Err(e) => tracing::warn!("Conversation won't be saved: {}", e),
+        }
+    }
+
+    let mut terminal = app::setup_terminal()?;
+    let result = app.run(&mut terminal).await;
+    app::restore_terminal(&mut terminal)?;
+    result
+}
+
+fn parse_model(name: &str) -> Result<String, String> {
+    // Unknown names are warned about once the app is up
+    claude::validate_model(name).map(|_| name.to_string())
+}
+
+fn parse_token_count(text: &str) -> Result<u64, String> {
+    budget::parse_token_count(text).ok_or_else(|| format!("invalid token count: {}", text))
+}
+
+fn parse_cost(text: &str) -> Result<f64, String> {
+    budget::parse_cost(text).ok_or_else(|| format!("invalid amount in USD: {}", text))
 }
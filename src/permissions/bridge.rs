@@ -0,0 +1,154 @@
+//! Stdio MCP server that relays permission prompts from the Claude CLI
+
+use anyhow::Result;
+use serde_json::{json, Value};
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::TcpStream,
+    sync::mpsc,
+};
+
+use super::{BridgeRequest, BridgeResponse, PERMISSION_SERVER_NAME, PERMISSION_TOKEN_ENV};
+
+/// Name of the tool exposed to the CLI (without the `mcp__server__` prefix)
+const TOOL_NAME: &str = "approve";
+
+/// Run the MCP server on stdin/stdout, forwarding tool calls to the broker at `addr`
+pub async fn run_bridge(addr: &str) -> Result<()> {
+    let token = std::env::var(PERMISSION_TOKEN_ENV)
+        .map_err(|_| anyhow::anyhow!("{} not set", PERMISSION_TOKEN_ENV))?;
+
+    // Responses can finish out of order, so a single task owns stdout
+    let (out_tx, mut out_rx) = mpsc::channel::<Value>(16);
+    let writer = tokio::spawn(async move {
+        let mut stdout = tokio::io::stdout();
+        while let Some(msg) = out_rx.recv().await {
+            let mut line = msg.to_string();
+            line.push('\n');
+            if stdout.write_all(line.as_bytes()).await.is_err() {
+                break;
+            }
+            let _ = stdout.flush().await;
+        }
+    });
+
+    let mut reader = BufReader::new(tokio::io::stdin());
+    let mut line = String::new();
+
+    loop {
+        line.clear();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        let Ok(msg) = serde_json::from_str::<Value>(line.trim()) else {
+            continue;
+        };
+        // Notifications carry no id and need no reply
+        let Some(id) = msg.get("id").cloned() else {
+            continue;
+        };
+        let method = msg.get("method").and_then(Value::as_str).unwrap_or_default();
+        let params = msg.get("params").cloned().unwrap_or(Value::Null);
+
+        match method {
+            "initialize" => {
+                let version = params
+                    .get("protocolVersion")
+                    .cloned()
+                    .unwrap_or_else(|| json!("2024-11-05"));
+                let result = json!({
+                    "protocolVersion": version,
+                    "capabilities": { "tools": {} },
+                    "serverInfo": {
+                        "name": PERMISSION_SERVER_NAME,
+                        "version": env!("CARGO_PKG_VERSION"),
+                    },
+                });
+                out_tx.send(reply(id, result)).await?;
+            }
+            "ping" => {
+                out_tx.send(reply(id, json!({}))).await?;
+            }
+            "tools/list" => {
+                out_tx.send(reply(id, json!({ "tools": [tool_definition()] }))).await?;
+            }
+            "tools/call" => {
+                let tx = out_tx.clone();
+                let addr = addr.to_string();
+                let token = token.clone();
+                tokio::spawn(async move {
+                    let arguments = params.get("arguments").cloned().unwrap_or(Value::Null);
+                    let response = match ask_broker(&addr, &token, &arguments).await {
+                        Ok(response) => response,
+                        Err(e) => BridgeResponse::Deny {
+                            message: format!("claude-terminal could not ask the user: {}", e),
+                        },
+                    };
+                    let text = serde_json::to_string(&response).unwrap_or_default();
+                    let result = json!({ "content": [{ "type": "text", "text": text }] });
+                    let _ = tx.send(reply(id, result)).await;
+                });
+            }
+            _ => {
+                let error = json!({
+                    "jsonrpc": "2.0",
+                    "id": id,
+                    "error": { "code": -32601, "message": format!("Method not found: {}", method) },
+                });
+                out_tx.send(error).await?;
+            }
+        }
+    }
+
+    drop(out_tx);
+    let _ = writer.await;
+    Ok(())
+}
+
+fn reply(id: Value, result: Value) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "result": result })
+}
+
+fn tool_definition() -> Value {
+    json!({
+        "name": TOOL_NAME,
+        "description": "Ask the claude-terminal user whether a tool call may run",
+        "inputSchema": {
+            "type": "object",
+            "properties": {
+                "tool_name": { "type": "string" },
+                "input": { "type": "object" },
+                "tool_use_id": { "type": "string" },
+            },
+            "required": ["tool_name", "input"],
+        },
+    })
+}
+
+/// Forward one permission request to the broker and wait for the decision
+async fn ask_broker(addr: &str, token: &str, arguments: &Value) -> Result<BridgeResponse> {
+    let request = BridgeRequest {
+        token: token.to_string(),
+        tool_name: arguments
+            .get("tool_name")
+            .and_then(Value::as_str)
+            .unwrap_or("unknown")
+            .to_string(),
+        input: arguments.get("input").cloned().unwrap_or(Value::Null),
+    };
+
+    let stream = TcpStream::connect(addr).await?;
+    let (read_half, mut write_half) = stream.into_split();
+
+    let mut line = serde_json::to_string(&request)?;
+    line.push('\n');
+    write_half.write_all(line.as_bytes()).await?;
+    write_half.flush().await?;
+
+    let mut reader = BufReader::new(read_half);
+    let mut response = String::new();
+    if reader.read_line(&mut response).await? == 0 {
+        anyhow::bail!("broker closed the connection");
+    }
+    Ok(serde_json::from_str(response.trim())?)
+}
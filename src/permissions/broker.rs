@@ -0,0 +1,109 @@
+//! TUI side of permission approval: accepts requests from the bridge
+
+use anyhow::Result;
+use tokio::{
+    io::{AsyncBufReadExt, AsyncWriteExt, BufReader},
+    net::{TcpListener, TcpStream},
+    sync::{mpsc, oneshot},
+};
+
+use crate::app::AppMessage;
+
+use super::{
+    BridgeRequest, BridgeResponse, PermissionDecision, PermissionRequest, PERMISSION_SERVER_NAME,
+    PERMISSION_TOKEN_ENV,
+};
+
+/// Listens on a loopback socket for permission requests from the bridge
+pub struct PermissionBroker {
+    addr: String,
+    token: String,
+}
+
+impl PermissionBroker {
+    /// Bind the listener and start accepting bridge connections
+    pub fn start(message_tx: mpsc::Sender<AppMessage>) -> Result<Self> {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0")?;
+        listener.set_nonblocking(true)?;
+        let addr = listener.local_addr()?.to_string();
+        let listener = TcpListener::from_std(listener)?;
+        let token = uuid::Uuid::new_v4().to_string();
+
+        let expected_token = token.clone();
+        tokio::spawn(async move {
+            loop {
+                let Ok((stream, _)) = listener.accept().await else {
+                    break;
+                };
+                let tx = message_tx.clone();
+                let token = expected_token.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, &token, tx).await {
+                        tracing::debug!("Permission bridge connection error: {}", e);
+                    }
+                });
+            }
+        });
+
+        Ok(Self { addr, token })
+    }
+
+    /// MCP config (inline JSON) that launches this binary as the bridge
+    pub fn mcp_config(&self) -> Result<String> {
+        let exe = std::env::current_exe()?;
+        let config = serde_json::json!({
+            "mcpServers": {
+                PERMISSION_SERVER_NAME: {
+                    "command": exe.to_string_lossy(),
+                    "args": ["--permission-bridge", self.addr],
+                    "env": { PERMISSION_TOKEN_ENV: self.token },
+                }
+            }
+        });
+        Ok(config.to_string())
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    token: &str,
+    tx: mpsc::Sender<AppMessage>,
+) -> Result<()> {
+    let (read_half, mut write_half) = stream.into_split();
+    let mut reader = BufReader::new(read_half);
+    let mut line = String::new();
+    reader.read_line(&mut line).await?;
+
+    let request: BridgeRequest = serde_json::from_str(line.trim())?;
+    if request.token != token {
+        anyhow::bail!("Permission bridge sent an invalid token");
+    }
+
+    let (responder, decision_rx) = oneshot::channel();
+    let input = request.input.clone();
+    tx.send(AppMessage::PermissionRequest(PermissionRequest {
+        tool_name: request.tool_name,
+        input: request.input,
+        responder,
+    }))
+    .await?;
+
+    // A dropped responder (app shutting down, turn interrupted) counts as a denial
+    let response = match decision_rx.await {
+        Ok(PermissionDecision::Allow | PermissionDecision::AlwaysAllow) => BridgeResponse::Allow {
+            updated_input: input,
+        },
+        Ok(PermissionDecision::Deny) => BridgeResponse::Deny {
+            message: "The user denied this tool call".to_string(),
+        },
+        Err(_) => BridgeResponse::Deny {
+            message: "The permission request was cancelled".to_string(),
+        },
+    };
+
+    let mut out = serde_json::to_string(&response)?;
+    out.push('\n');
+    write_half.write_all(out.as_bytes()).await?;
+    write_half.flush().await?;
+    Ok(())
+}
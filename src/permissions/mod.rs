@@ -0,0 +1,16 @@
+//! Interactive tool permission approval
+//!
+//! The Claude CLI asks a small stdio MCP server (this binary, run with
+//! `--permission-bridge`) whether each tool call may run. The bridge relays
+//! the request to the TUI's broker over a loopback socket and waits for the
+//! user's answer.
+
+mod bridge;
+mod broker;
+mod rules;
+mod types;
+
+pub use bridge::*;
+pub use broker::*;
+pub use rules::*;
+pub use types::*;
@@ -0,0 +1,80 @@
+//! Project-level "always allow" rules
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Rules saved in `.claude-terminal/permissions.json` in the project directory
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PermissionRules {
+    /// Rules that are approved without asking, e.g. `Read` or `Bash(cargo test)`
+    #[serde(default)]
+    pub allow: Vec<String>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl PermissionRules {
+    /// Load the rules for a project, or start empty if there are none
+    pub fn load(project_dir: &Path) -> Self {
+        let path = project_dir.join(".claude-terminal").join("permissions.json");
+        let mut rules: Self = std::fs::read_to_string(&path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        rules.path = path;
+        rules
+    }
+
+    /// Check whether a tool call is covered by a saved rule
+    pub fn allows(&self, tool_name: &str, input: &serde_json::Value) -> bool {
+        let rule = rule_for(tool_name, input);
+        self.allow.iter().any(|r| r == tool_name || *r == rule)
+    }
+
+    /// Remember a tool call as always allowed and save the file
+    pub fn add(&mut self, tool_name: &str, input: &serde_json::Value) -> Result<String> {
+        let rule = rule_for(tool_name, input);
+        if !self.allow.contains(&rule) {
+            self.allow.push(rule.clone());
+        }
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir)?;
+        }
+        std::fs::write(&self.path, serde_json::to_string_pretty(self)?)?;
+        Ok(rule)
+    }
+}
+
+/// Build the rule for a tool call. Shell commands are scoped to the exact
+/// command so approving one doesn't approve every command.
+pub fn rule_for(tool_name: &str, input: &serde_json::Value) -> String {
+    match input.get("command").and_then(|c| c.as_str()) {
+        Some(command) if tool_name == "Bash" => format!("Bash({})", command),
+        _ => tool_name.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rule_for_bash_is_scoped_to_command() {
+        assert_eq!(rule_for("Bash", &json!({"command": "cargo test"})), "Bash(cargo test)");
+        assert_eq!(rule_for("Read", &json!({"file_path": "src/main.rs"})), "Read");
+    }
+
+    #[test]
+    fn test_allows_matches_tool_or_scoped_rule() {
+        let rules = PermissionRules {
+            allow: vec!["Read".to_string(), "Bash(ls)".to_string()],
+            ..Default::default()
+        };
+        assert!(rules.allows("Read", &json!({"file_path": "a"})));
+        assert!(rules.allows("Bash", &json!({"command": "ls"})));
+        assert!(!rules.allows("Bash", &json!({"command": "rm -rf /"})));
+        assert!(!rules.allows("Write", &json!({})));
+    }
+}
@@ -0,0 +1,55 @@
+//! Types shared by the permission bridge and broker
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::oneshot;
+
+/// MCP server name registered with the Claude CLI
+pub const PERMISSION_SERVER_NAME: &str = "claude_terminal";
+
+/// Fully qualified tool name passed to `--permission-prompt-tool`
+pub const PERMISSION_TOOL: &str = "mcp__claude_terminal__approve";
+
+/// Environment variable carrying the broker's auth token to the bridge
+pub const PERMISSION_TOKEN_ENV: &str = "CLAUDE_TERMINAL_PERMISSION_TOKEN";
+
+/// A tool call waiting for the user's decision
+#[derive(Debug)]
+pub struct PermissionRequest {
+    pub tool_name: String,
+    pub input: serde_json::Value,
+    pub responder: oneshot::Sender<PermissionDecision>,
+}
+
+/// The user's answer to a permission request
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermissionDecision {
+    /// Allow this call only
+    Allow,
+    /// Allow and remember the rule for this project
+    AlwaysAllow,
+    /// Refuse the call
+    Deny,
+}
+
+/// Request sent from the bridge to the broker (one JSON line)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BridgeRequest {
+    pub token: String,
+    pub tool_name: String,
+    #[serde(default)]
+    pub input: serde_json::Value,
+}
+
+/// Response sent from the broker to the bridge, in the shape the CLI expects
+/// from a permission prompt tool
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "behavior", rename_all = "lowercase")]
+pub enum BridgeResponse {
+    Allow {
+        #[serde(rename = "updatedInput")]
+        updated_input: serde_json::Value,
+    },
+    Deny {
+        message: String,
+    },
+}
@@ -3,7 +3,7 @@
 use anyhow::Result;
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use tokio::{
     fs,
     sync::mpsc,
@@ -57,7 +57,7 @@ impl SessionManager {
     }
 
     /// Register this session
-    pub async fn register(&mut self, task: &str) -> Result<String> {
+    pub async fn register(&mut self, task: &str, cwd: &Path) -> Result<String> {
         let pid = std::process::id();
         let timestamp = Utc::now().timestamp();
         let session_id = format!("claude-terminal-{}-{}", pid, timestamp);
@@ -65,7 +65,7 @@ impl SessionManager {
         let info = SessionInfo {
             id: session_id.clone(),
             pid,
-            cwd: std::env::current_dir()?.to_string_lossy().to_string(),
+            cwd: cwd.to_string_lossy().to_string(),
             task: task.to_string(),
             started: Utc::now(),
             app: Some("claude-terminal".to_string()),
@@ -84,6 +84,19 @@ impl SessionManager {
         Ok(session_id)
     }
 
+    /// Record a new working directory in the session file
+    pub async fn update_cwd(&self, cwd: &Path) -> Result<()> {
+        let Some(ref session_id) = self.session_id else {
+            return Ok(());
+        };
+        let path = self.sessions_dir.join(format!("{}.json", session_id));
+        let content = fs::read_to_string(&path).await?;
+        let mut info: SessionInfo = serde_json::from_str(&content)?;
+        info.cwd = cwd.to_string_lossy().to_string();
+        fs::write(&path, serde_json::to_string_pretty(&info)?).await?;
+        Ok(())
+    }
+
     /// Deregister this session
     pub async fn deregister(&self) -> Result<()> {
         if let Some(ref session_id) = self.session_id {
@@ -228,7 +241,7 @@ impl SessionManager {
 }
 
 /// Check if a process is alive
-fn is_process_alive(pid: u32) -> bool {
+pub fn is_process_alive(pid: u32) -> bool {
     #[cfg(unix)]
     {
         use std::os::unix::process::CommandExt;
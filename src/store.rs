@@ -0,0 +1,250 @@
+//! Conversation state saved while running, so it can be restored after a
+//! crash
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use crate::app::ConversationEntry;
+use crate::sessions::is_process_alive;
+
+/// Finished conversation entries, one JSON object per line
+const ENTRIES_FILE: &str = "entries.jsonl";
+
+/// Latest snapshot of everything else
+const SNAPSHOT_FILE: &str = "snapshot.json";
+
+/// Left-over state older than this is deleted instead of offered
+const STALE_AFTER_DAYS: u64 = 7;
+
+/// Directory holding the state of every running instance
+pub fn state_root() -> Option<PathBuf> {
+    dirs::data_local_dir().map(|dir| dir.join("claude-terminal").join("state"))
+}
+
+/// What isn't in the conversation entries: where we were, the Claude
+/// session, and unsent input
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Snapshot {
+    pub pid: u32,
+    pub working_dir: PathBuf,
+    /// Claude CLI session to resume
+    pub claude_session_id: Option<String>,
+    /// Draft in the input box
+    pub input: String,
+    pub cursor_position: usize,
+    pub message_queue: Vec<String>,
+    pub input_history: Vec<String>,
+}
+
+/// This instance's saved state, removed again on a clean exit
+#[derive(Debug)]
+pub struct StateStore {
+    dir: PathBuf,
+    /// Entries written so far
+    saved_entries: usize,
+    last_snapshot: Option<Snapshot>,
+}
+
+impl StateStore {
+    /// Start an empty store under `root`
+    pub fn create(root: &Path) -> Result<Self> {
+        let dir = root.join(uuid::Uuid::new_v4().to_string());
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(Self {
+            dir,
+            saved_entries: 0,
+            last_snapshot: None,
+        })
+    }
+
+    /// Number of conversation entries saved
+    pub fn saved_entries(&self) -> usize {
+        self.saved_entries
+    }
+
+    /// Append finished entries
+    pub fn append(&mut self, entries: &[ConversationEntry]) -> Result<()> {
+        if entries.is_empty() {
+            return Ok(());
+        }
+        let mut lines = String::new();
+        for entry in entries {
+            lines.push_str(&serde_json::to_string(entry)?);
+            lines.push('\n');
+        }
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.dir.join(ENTRIES_FILE))?;
+        file.write_all(lines.as_bytes())?;
+        self.saved_entries += entries.len();
+        Ok(())
+    }
+
+    /// Forget the saved entries, after the conversation was cleared or
+    /// replaced
+    pub fn clear_entries(&mut self) -> Result<()> {
+        std::fs::write(self.dir.join(ENTRIES_FILE), "")?;
+        self.saved_entries = 0;
+        Ok(())
+    }
+
+    /// Save `snapshot` if it changed since the last one
+    pub fn save_snapshot(&mut self, snapshot: Snapshot) -> Result<()> {
+        if self.last_snapshot.as_ref() == Some(&snapshot) {
+            return Ok(());
+        }
+        // Written aside and renamed, so a crash mid-write keeps the old one
+        let path = self.dir.join(SNAPSHOT_FILE);
+        let temp = path.with_extension("json.tmp");
+        std::fs::write(&temp, serde_json::to_string(&snapshot)?)?;
+        std::fs::rename(&temp, &path)?;
+        self.last_snapshot = Some(snapshot);
+        Ok(())
+    }
+
+    /// Delete the saved state
+    pub fn remove(self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+            tracing::debug!("Failed to remove {}: {}", self.dir.display(), e);
+        }
+    }
+}
+
+/// State left behind by an instance that didn't exit cleanly
+#[derive(Debug, Clone)]
+pub struct SavedState {
+    dir: PathBuf,
+    pub snapshot: Snapshot,
+    pub entries: Vec<ConversationEntry>,
+}
+
+impl SavedState {
+    /// The most recent state left in `root` by a dead instance that was
+    /// working in `working_dir`. Stale state from any directory is deleted
+    /// along the way.
+    pub fn find(root: &Path, working_dir: &Path) -> Option<SavedState> {
+        let entries = std::fs::read_dir(root).ok()?;
+        let mut found: Option<(std::time::SystemTime, SavedState)> = None;
+
+        for dir in entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()) {
+            let Some(snapshot) = read_snapshot(&dir) else {
+                continue;
+            };
+            if is_process_alive(snapshot.pid) {
+                continue;
+            }
+            let modified = std::fs::metadata(dir.join(SNAPSHOT_FILE))
+                .and_then(|metadata| metadata.modified())
+                .unwrap_or(std::time::UNIX_EPOCH);
+            let age = modified.elapsed().unwrap_or_default();
+            if age.as_secs() > STALE_AFTER_DAYS * 24 * 60 * 60 {
+                let _ = std::fs::remove_dir_all(&dir);
+                continue;
+            }
+            if snapshot.working_dir != working_dir
+                || found.as_ref().is_some_and(|(newest, _)| *newest >= modified)
+            {
+                continue;
+            }
+            let entries = read_entries(&dir);
+            found = Some((modified, SavedState { dir, snapshot, entries }));
+        }
+
+        found
+            .map(|(_, state)| state)
+            .filter(|state| !state.is_empty())
+    }
+
+    /// Nothing worth restoring
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+            && self.snapshot.input.is_empty()
+            && self.snapshot.message_queue.is_empty()
+    }
+
+    /// Delete the saved state once it's been restored or declined
+    pub fn discard(&self) {
+        if let Err(e) = std::fs::remove_dir_all(&self.dir) {
+            tracing::debug!("Failed to remove {}: {}", self.dir.display(), e);
+        }
+    }
+}
+
+fn read_snapshot(dir: &Path) -> Option<Snapshot> {
+    let content = std::fs::read_to_string(dir.join(SNAPSHOT_FILE)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+/// Saved entries; a line cut short by the crash is skipped
+fn read_entries(dir: &Path) -> Vec<ConversationEntry> {
+    std::fs::read_to_string(dir.join(ENTRIES_FILE))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::{ConversationContent, Role};
+
+    #[test]
+    fn test_saved_state_found_after_unclean_exit() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let working_dir = PathBuf::from("/work/project");
+        let entry = |text: &str| {
+            ConversationEntry::new(Role::User, ConversationContent::Text(text.to_string()))
+        };
+
+        let mut store = StateStore::create(root).unwrap();
+        store.append(&[entry("one"), entry("two")]).unwrap();
+        store.append(&[entry("three")]).unwrap();
+        let snapshot = Snapshot {
+            // Not a running process
+            pid: u32::MAX / 2,
+            working_dir: working_dir.clone(),
+            input: "half-typed".to_string(),
+            ..Default::default()
+        };
+        store.save_snapshot(snapshot.clone()).unwrap();
+        // A line cut short by a crash
+        let mut file = std::fs::OpenOptions::new()
+            .append(true)
+            .open(store.dir.join(ENTRIES_FILE))
+            .unwrap();
+        file.write_all(b"{\"role\":\"Us").unwrap();
+
+        assert!(SavedState::find(root, Path::new("/work/other")).is_none());
+        let saved = SavedState::find(root, &working_dir).unwrap();
+        assert_eq!(saved.entries.len(), 3);
+        assert_eq!(saved.snapshot, snapshot);
+
+        saved.discard();
+        assert!(SavedState::find(root, &working_dir).is_none());
+    }
+
+    #[test]
+    fn test_running_instance_not_offered() {
+        let temp = tempfile::tempdir().unwrap();
+        let root = temp.path();
+        let mut store = StateStore::create(root).unwrap();
+        store
+            .save_snapshot(Snapshot {
+                pid: std::process::id(),
+                working_dir: PathBuf::from("/work"),
+                input: "draft".to_string(),
+                ..Default::default()
+            })
+            .unwrap();
+
+        assert!(SavedState::find(root, Path::new("/work")).is_none());
+        store.remove();
+    }
+}
@@ -0,0 +1,52 @@
+//! Offer to compact a conversation that is filling the context window
+
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use super::styles;
+
+/// Draw the /compact offer centered over `area`; `used` is the fraction of
+/// the context window in use
+pub fn draw_compact_offer(frame: &mut Frame, area: Rect, used: f64) {
+    let lines = vec![
+        Line::from(Span::styled(
+            format!("The conversation is using {:.0}% of the context window.", used * 100.0),
+            styles::input_style(),
+        )),
+        Line::from(Span::styled(
+            "Compacting now summarizes it on your terms, before Claude has to do it itself.",
+            styles::status_style(),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("[y]", styles::token_style()),
+            Span::styled(" Run /compact  ", styles::status_style()),
+            Span::styled("[n]", styles::error_style()),
+            Span::styled(" Not now", styles::status_style()),
+        ]),
+    ];
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(styles::busy_style())
+        .title(" Context almost full ");
+
+    let width = area.width.saturating_sub(4).min(84);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(Text::from(lines)).block(block).wrap(Wrap { trim: false }),
+        popup,
+    );
+}
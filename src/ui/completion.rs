@@ -0,0 +1,67 @@
+//! Slash command completion popup
+
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use super::styles;
+
+/// Most commands listed at once
+const MAX_ROWS: usize = 8;
+
+/// A command matching what has been typed
+#[derive(Debug, Clone, PartialEq)]
+pub struct Completion<'a> {
+    pub name: &'a str,
+    /// Arguments, e.g. `<path>`
+    pub hint: Option<&'a str>,
+    pub description: &'a str,
+    /// Where a Claude command comes from, e.g. `project`
+    pub origin: Option<String>,
+}
+
+/// Draw the matching commands just above `input_area`
+pub fn draw_completions(frame: &mut Frame, input_area: Rect, completions: &[Completion]) {
+    let mut lines: Vec<Line> = completions
+        .iter()
+        .take(MAX_ROWS)
+        .map(|completion| {
+            let usage = match completion.hint {
+                Some(hint) => format!("/{} {}", completion.name, hint),
+                None => format!("/{}", completion.name),
+            };
+            let mut spans = vec![
+                Span::styled(format!(" {:<24} ", usage), styles::input_style()),
+                Span::styled(completion.description.to_string(), styles::status_style()),
+            ];
+            if let Some(origin) = &completion.origin {
+                spans.push(Span::styled(format!(" ({})", origin), styles::system_style()));
+            }
+            Line::from(spans)
+        })
+        .collect();
+    if completions.len() > MAX_ROWS {
+        lines.push(Line::from(Span::styled(
+            format!(" ... {} more", completions.len() - MAX_ROWS),
+            styles::system_style(),
+        )));
+    }
+
+    let height = (lines.len() as u16 + 2).min(input_area.y);
+    let popup = Rect {
+        x: input_area.x,
+        y: input_area.y.saturating_sub(height),
+        width: input_area.width.min(90),
+        height,
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(styles::border_style())
+        .title_bottom(" Tab complete ");
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(Text::from(lines)).block(block), popup);
+}
@@ -8,9 +8,10 @@ use ratatui::{
     Frame,
 };
 
-use crate::app::{ConversationContent, Role};
+use crate::app::{ConversationContent, ConversationEntry, Role, ToolStatus};
+use crate::claude::{key_fields, split_mcp_tool_name};
 
-use super::{styles, RenderState};
+use super::{format_duration, language_for_path, render_markdown, styles, Highlighter, RenderState};
 
 /// Draw the conversation area
 pub fn draw_conversation(frame: &mut Frame, area: Rect, state: &RenderState) {
@@ -25,103 +26,7 @@ pub fn draw_conversation(frame: &mut Frame, area: Rect, state: &RenderState) {
     let mut lines: Vec<Line> = Vec::new();
 
     for entry in state.messages {
-        let (prefix, style) = match entry.role {
-            Role::User => ("[You]", styles::user_style()),
-            Role::Assistant => ("[Claude]", styles::assistant_style()),
-            Role::System => ("[System]", styles::system_style()),
-            Role::Tool => ("[Tool]", styles::tool_style()),
-            Role::Bash => ("[Bash]", styles::bash_style()),
-        };
-
-        match &entry.content {
-            ConversationContent::Text(text) => {
-                // Add role header
-                lines.push(Line::from(vec![
-                    Span::styled(format!("{}", prefix), style),
-                ]));
-                // Add content with word wrapping handled by Paragraph
-                for line in text.lines() {
-                    lines.push(Line::from(Span::styled(line.to_string(), Style::default().fg(styles::TEXT))));
-                }
-                lines.push(Line::from(""));
-            }
-            ConversationContent::ToolUse { name, input } => {
-                lines.push(Line::from(vec![
-                    Span::styled(format!("{} ", prefix), style),
-                    Span::styled(name, styles::tool_style().add_modifier(Modifier::BOLD)),
-                ]));
-                // Truncate long inputs
-                let display_input = if input.len() > 200 {
-                    format!("{}...", &input[..200])
-                } else {
-                    input.clone()
-                };
-                lines.push(Line::from(Span::styled(
-                    format!("  {}", display_input),
-                    styles::tool_style(),
-                )));
-                lines.push(Line::from(""));
-            }
-            ConversationContent::ToolResult { name, result } => {
-                lines.push(Line::from(vec![
-                    Span::styled(format!("{} result: ", name), styles::tool_result_style()),
-                ]));
-                // Truncate long results
-                let display_result = if result.len() > 500 {
-                    format!("{}...", &result[..500])
-                } else {
-                    result.clone()
-                };
-                for line in display_result.lines().take(10) {
-                    lines.push(Line::from(Span::styled(
-                        format!("  {}", line),
-                        styles::tool_result_style(),
-                    )));
-                }
-                lines.push(Line::from(""));
-            }
-            ConversationContent::Thinking(text) => {
-                lines.push(Line::from(vec![
-                    Span::styled("Thinking: ", styles::thinking_style()),
-                ]));
-                // Show truncated thinking
-                let display = if text.len() > 300 {
-                    format!("{}...", &text[..300])
-                } else {
-                    text.clone()
-                };
-                lines.push(Line::from(Span::styled(display, styles::thinking_style())));
-                lines.push(Line::from(""));
-            }
-            ConversationContent::BashCommand {
-                command,
-                output,
-                exit_code,
-            } => {
-                lines.push(Line::from(vec![
-                    Span::styled("$ ", styles::bash_style()),
-                    Span::styled(command, styles::bash_style().add_modifier(Modifier::BOLD)),
-                ]));
-                // Show output
-                for line in output.lines().take(20) {
-                    lines.push(Line::from(Span::styled(line.to_string(), Style::default().fg(styles::TEXT))));
-                }
-                if output.lines().count() > 20 {
-                    lines.push(Line::from(Span::styled(
-                        "  ... (output truncated)",
-                        styles::system_style(),
-                    )));
-                }
-                // Show exit code if non-zero
-                if *exit_code != 0 {
-                    lines.push(Line::from(Span::styled(
-                        format!("(exit code: {})", exit_code),
-                        styles::error_style(),
-                    )));
-                }
-                lines.push(Line::from(""));
-            }
-        }
+        push_entry_lines(&mut lines, entry, state.expand_subagents, state.highlighter);
     }
 
     // Add streaming buffer if present
@@ -129,9 +34,8 @@ pub fn draw_conversation(frame: &mut Frame, area: Rect, state: &RenderState) {
         lines.push(Line::from(vec![
             Span::styled("[Claude]", styles::assistant_style()),
         ]));
-        for line in state.streaming_buffer.lines() {
-            lines.push(Line::from(Span::styled(line.to_string(), Style::default().fg(styles::TEXT))));
-        }
+        // Still changing, so rendered afresh rather than cached
+        lines.extend(render_markdown(state.streaming_buffer, state.highlighter));
         // Show typing indicator
         lines.push(Line::from(Span::styled("...", styles::busy_style())));
     }
@@ -153,3 +57,393 @@ pub fn draw_conversation(frame: &mut Frame, area: Rect, state: &RenderState) {
 
     frame.render_widget(paragraph, area);
 }
+
+/// Add the lines for one conversation entry
+fn push_entry_lines<'a>(
+    lines: &mut Vec<Line<'a>>,
+    entry: &'a ConversationEntry,
+    expand_subagents: bool,
+    highlighter: &Highlighter,
+) {
+    let (prefix, style) = match entry.role {
+        Role::User => ("[You]", styles::user_style()),
+        Role::Assistant => ("[Claude]", styles::assistant_style()),
+        Role::System => ("[System]", styles::system_style()),
+        Role::Tool => ("[Tool]", styles::tool_style()),
+        Role::Bash => ("[Bash]", styles::bash_style()),
+    };
+
+    match &entry.content {
+        ConversationContent::Text(text) => {
+            // Add role header
+            lines.push(Line::from(vec![
+                Span::styled(format!("{}", prefix), style),
+            ]));
+            // Add content with word wrapping handled by Paragraph. Claude
+            // writes Markdown; everything else is shown as typed.
+            if matches!(entry.role, Role::Assistant) {
+                lines.extend(highlighter.markdown(text));
+            } else {
+                for line in text.lines() {
+                    let style = Style::default().fg(styles::TEXT);
+                    lines.push(Line::from(Span::styled(line.to_string(), style)));
+                }
+            }
+            lines.push(Line::from(""));
+        }
+        ConversationContent::ToolUse {
+            name,
+            input,
+            status,
+            result,
+            finished_at,
+            ..
+        } => {
+            // Pending calls show a live timer
+            let end = finished_at.unwrap_or_else(chrono::Utc::now);
+            let elapsed = (end - entry.timestamp).num_milliseconds().max(0) as u64;
+            let (marker, marker_style) = match status {
+                ToolStatus::Pending => ("running", styles::busy_style()),
+                ToolStatus::Success => ("done", styles::tool_result_style()),
+                ToolStatus::Error => ("failed", styles::error_style()),
+                ToolStatus::Interrupted => ("interrupted", styles::system_style()),
+            };
+            let mut header = vec![Span::styled(format!("{} ", prefix), style)];
+            header.extend(tool_name_spans(name));
+            header.push(Span::styled(
+                format!("  {} {}", marker, format_duration(elapsed)),
+                marker_style,
+            ));
+            lines.push(Line::from(header));
+            draw_tool_input(lines, input, *status == ToolStatus::Pending);
+            // Show the result under its invocation; files Claude read are
+            // highlighted
+            let read_file = match (name.as_str(), status, result) {
+                ("Read", ToolStatus::Success, Some(result)) => read_file_path(input)
+                    .zip(numbered_file_lines(result, TOOL_RESULT_LINES)),
+                _ => None,
+            };
+            if let Some((path, (first_line, code))) = read_file {
+                let language = language_for_path(&path);
+                for mut line in highlighter.code_block(&code, language, first_line) {
+                    line.spans.insert(0, Span::raw("  "));
+                    lines.push(line);
+                }
+            } else if let Some(result) = result {
+                let result_style = if *status == ToolStatus::Error {
+                    styles::error_style()
+                } else {
+                    styles::tool_result_style()
+                };
+                for line in truncate(result, 500).lines().take(TOOL_RESULT_LINES) {
+                    lines.push(Line::from(Span::styled(format!("  > {}", line), result_style)));
+                }
+            }
+            lines.push(Line::from(""));
+        }
+        ConversationContent::ToolResult { name, result } => {
+            lines.push(Line::from(vec![
+                Span::styled(format!("{} result: ", name), styles::tool_result_style()),
+            ]));
+            // Truncate long results
+            for line in truncate(result, 500).lines().take(TOOL_RESULT_LINES) {
+                lines.push(Line::from(Span::styled(
+                    format!("  {}", line),
+                    styles::tool_result_style(),
+                )));
+            }
+            lines.push(Line::from(""));
+        }
+        ConversationContent::Thinking(text) => {
+            lines.push(Line::from(vec![
+                Span::styled("Thinking: ", styles::thinking_style()),
+            ]));
+            // Show truncated thinking
+            lines.push(Line::from(Span::styled(truncate(text, 300), styles::thinking_style())));
+            lines.push(Line::from(""));
+        }
+        ConversationContent::BashCommand {
+            command,
+            output,
+            exit_code,
+            ..
+        } => {
+            lines.push(Line::from(vec![
+                Span::styled("$ ", styles::bash_style()),
+                Span::styled(command, styles::bash_style().add_modifier(Modifier::BOLD)),
+            ]));
+            // Show output, highlighted when it is a file from `cat`
+            let shown: Vec<&str> = output.lines().take(BASH_OUTPUT_LINES).collect();
+            match cat_file(command).filter(|_| *exit_code == Some(0)) {
+                Some(path) => {
+                    let code = shown.join("\n");
+                    lines.extend(highlighter.code_block(&code, language_for_path(path), 1));
+                }
+                None => {
+                    for line in shown {
+                        let style = Style::default().fg(styles::TEXT);
+                        lines.push(Line::from(Span::styled(line.to_string(), style)));
+                    }
+                }
+            }
+            if output.lines().count() > BASH_OUTPUT_LINES {
+                lines.push(Line::from(Span::styled(
+                    "  ... (output truncated)",
+                    styles::system_style(),
+                )));
+            }
+            match exit_code {
+                None => lines.push(Line::from(Span::styled("running...", styles::busy_style()))),
+                // Show exit code if non-zero
+                Some(code) if *code != 0 => lines.push(Line::from(Span::styled(
+                    format!("(exit code: {})", code),
+                    styles::error_style(),
+                ))),
+                Some(_) => {}
+            }
+            lines.push(Line::from(""));
+        }
+    }
+
+    if !entry.children.is_empty() {
+        push_subagent_lines(lines, entry, expand_subagents, highlighter);
+    }
+}
+
+/// Add a subagent's status line and, when expanded, its own transcript
+fn push_subagent_lines<'a>(
+    lines: &mut Vec<Line<'a>>,
+    entry: &'a ConversationEntry,
+    expand: bool,
+    highlighter: &Highlighter,
+) {
+    // Drop the blank separator so the block sits under its Task call
+    if lines.last().is_some_and(|line| line.width() == 0) {
+        lines.pop();
+    }
+
+    let tool_calls = entry
+        .children
+        .iter()
+        .filter(|child| matches!(child.content, ConversationContent::ToolUse { .. }))
+        .count();
+    let running = matches!(
+        entry.content,
+        ConversationContent::ToolUse {
+            status: ToolStatus::Pending,
+            ..
+        }
+    );
+    let (state, state_style) = if running {
+        ("running", styles::busy_style())
+    } else {
+        ("finished", styles::tool_result_style())
+    };
+    let last_activity = entry.children.last().map(activity_summary).unwrap_or_default();
+    lines.push(Line::from(vec![
+        Span::styled("  subagent: ", styles::status_style()),
+        Span::styled(
+            format!(
+                "{}, {} tool call{}",
+                state,
+                tool_calls,
+                if tool_calls == 1 { "" } else { "s" }
+            ),
+            state_style,
+        ),
+        Span::styled(format!("  {}", last_activity), styles::system_style()),
+    ]));
+
+    if expand {
+        for child in &entry.children {
+            let mut child_lines = Vec::new();
+            push_entry_lines(&mut child_lines, child, expand, highlighter);
+            // Keep the block compact: no blank separators inside it
+            for mut line in child_lines.into_iter().filter(|line| line.width() > 0) {
+                line.spans.insert(0, Span::styled("  | ", styles::border_style()));
+                lines.push(line);
+            }
+        }
+    } else {
+        lines.push(Line::from(Span::styled(
+            "  (Ctrl+O to expand subagents)",
+            styles::system_style(),
+        )));
+    }
+    lines.push(Line::from(""));
+}
+
+/// Tool name spans; MCP tools show their server dimmed before the tool
+fn tool_name_spans(name: &str) -> Vec<Span<'static>> {
+    let tool_style = styles::tool_style().add_modifier(Modifier::BOLD);
+    match split_mcp_tool_name(name) {
+        Some((server, tool)) => vec![
+            Span::styled(format!("{} ", server), styles::system_style()),
+            Span::styled(tool.to_string(), tool_style),
+        ],
+        None => vec![Span::styled(name.to_string(), tool_style)],
+    }
+}
+
+/// Tool name as plain text, e.g. `github/create_issue` for MCP tools
+pub fn display_tool_name(name: &str) -> String {
+    match split_mcp_tool_name(name) {
+        Some((server, tool)) => format!("{}/{}", server, tool),
+        None => name.to_string(),
+    }
+}
+
+/// One-line description of a subagent's latest step
+fn activity_summary(entry: &ConversationEntry) -> String {
+    match &entry.content {
+        ConversationContent::ToolUse { name, input, .. } => match key_fields(input).first() {
+            Some(field) => {
+                let first_line = field.value.lines().next().unwrap_or_default();
+                format!("{} {}", display_tool_name(name), truncate(first_line, 60))
+            }
+            None => display_tool_name(name),
+        },
+        ConversationContent::Text(text) | ConversationContent::Thinking(text) => {
+            truncate(text.lines().last().unwrap_or_default(), 60)
+        }
+        _ => String::new(),
+    }
+}
+
+/// Lines of a multi-line tool input field shown in the view
+const TOOL_FIELD_LINES: usize = 4;
+
+/// Lines of a tool result shown in the view
+const TOOL_RESULT_LINES: usize = 10;
+
+/// Lines of bash output shown in the view
+const BASH_OUTPUT_LINES: usize = 20;
+
+/// File a `Read` call is for
+fn read_file_path(input: &str) -> Option<String> {
+    let input: serde_json::Value = serde_json::from_str(input).ok()?;
+    input.get("file_path")?.as_str().map(str::to_string)
+}
+
+/// First line number and text of a `Read` result, which numbers each line
+/// like `cat -n`, keeping at most `max` lines
+fn numbered_file_lines(result: &str, max: usize) -> Option<(usize, String)> {
+    let mut first_line = None;
+    let mut code = String::new();
+    for line in result.lines().take(max) {
+        let line = line.trim_start();
+        let digits = line.len() - line.trim_start_matches(|c: char| c.is_ascii_digit()).len();
+        let rest = &line[digits..];
+        let Some(text) = rest.strip_prefix('→').or_else(|| rest.strip_prefix('\t')) else {
+            break;
+        };
+        let Ok(number) = line[..digits].parse() else {
+            break;
+        };
+        first_line.get_or_insert(number);
+        code.push_str(text);
+        code.push('\n');
+    }
+    first_line.map(|first_line| (first_line, code))
+}
+
+/// File printed by a plain `cat <file>` command
+fn cat_file(command: &str) -> Option<&str> {
+    let mut words = command.split_whitespace();
+    let (Some("cat"), Some(file), None) = (words.next(), words.next(), words.next()) else {
+        return None;
+    };
+    let special = |c: char| "|;&<>$`*?".contains(c);
+    if file.starts_with('-') || file.contains(special) {
+        return None;
+    }
+    Some(file.trim_matches(|c| c == '\'' || c == '"'))
+}
+
+/// Show the key fields of a tool input, reading it tolerantly so calls
+/// update live while their input streams
+fn draw_tool_input(lines: &mut Vec<Line>, input: &str, pending: bool) {
+    let fields = key_fields(input);
+    if fields.is_empty() {
+        // Truncate long inputs
+        lines.push(Line::from(Span::styled(
+            format!("  {}", truncate(input, 200)),
+            styles::tool_style(),
+        )));
+        return;
+    }
+
+    for field in fields {
+        let field_lines: Vec<&str> = field.value.lines().collect();
+        if field_lines.len() <= 1 {
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {}: ", field.key), styles::status_style()),
+                Span::styled(truncate(&field.value, 200), styles::tool_style()),
+            ]));
+            continue;
+        }
+
+        let streaming = pending && !field.complete;
+        lines.push(Line::from(vec![
+            Span::styled(format!("  {}: ", field.key), styles::status_style()),
+            Span::styled(
+                format!("{} lines{}", field_lines.len(), if streaming { "..." } else { "" }),
+                styles::system_style(),
+            ),
+        ]));
+        // Follow the tail while streaming, otherwise show the start
+        let shown = if streaming {
+            &field_lines[field_lines.len().saturating_sub(TOOL_FIELD_LINES)..]
+        } else {
+            &field_lines[..TOOL_FIELD_LINES.min(field_lines.len())]
+        };
+        for line in shown {
+            lines.push(Line::from(Span::styled(
+                format!("    {}", truncate(line, 200)),
+                styles::tool_style(),
+            )));
+        }
+    }
+}
+
+/// Truncate to at most `max` bytes on a char boundary, adding an ellipsis
+fn truncate(text: &str, max: usize) -> String {
+    if text.len() <= max {
+        return text.to_string();
+    }
+    let mut end = max;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!("{}...", &text[..end])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_non_ascii_output_truncated_on_char_boundary() {
+        let highlighter = Highlighter::default();
+        // Byte 500 of the result and byte 300 of the thinking fall inside
+        // a character
+        let result = ConversationEntry::new(
+            Role::Tool,
+            ConversationContent::ToolResult {
+                name: "Grep".to_string(),
+                result: "日本".repeat(100),
+            },
+        );
+        let thinking = ConversationEntry::new(
+            Role::Assistant,
+            ConversationContent::Thinking(format!("x{}", "é".repeat(200))),
+        );
+
+        let mut lines = Vec::new();
+        push_entry_lines(&mut lines, &result, false, &highlighter);
+        push_entry_lines(&mut lines, &thinking, false, &highlighter);
+        let text: Vec<String> = lines.iter().map(|line| line.to_string()).collect();
+        assert_eq!(text[1], format!("  {}...", "日本".repeat(83)));
+        assert_eq!(text[4], format!("x{}...", "é".repeat(149)));
+    }
+}
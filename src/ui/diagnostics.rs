@@ -0,0 +1,99 @@
+//! Diagnostics panel for the Claude CLI
+
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::app::Diagnostics;
+
+use super::styles;
+
+/// Draw the diagnostics panel over most of `area`. `scroll` counts stderr
+/// lines up from the newest.
+pub fn draw_diagnostics(frame: &mut Frame, area: Rect, diagnostics: &Diagnostics, scroll: usize) {
+    let mut lines: Vec<Line> = Vec::new();
+    let label = |text: &'static str| Span::styled(text, styles::status_style());
+
+    lines.push(Line::from(vec![
+        label("Executable: "),
+        Span::styled(diagnostics.claude_bin.clone(), styles::input_style()),
+    ]));
+    match &diagnostics.preflight {
+        Some(report) => {
+            lines.push(Line::from(vec![
+                label("Version:    "),
+                Span::styled(
+                    report.version.clone().unwrap_or_else(|| "unknown".to_string()),
+                    styles::input_style(),
+                ),
+            ]));
+            if report.problems.is_empty() {
+                lines.push(Line::from(vec![
+                    label("Checks:     "),
+                    Span::styled("ok", styles::tool_result_style()),
+                ]));
+            }
+            for problem in &report.problems {
+                lines.push(Line::from(vec![
+                    label("Problem:    "),
+                    Span::styled(problem.clone(), styles::error_style()),
+                ]));
+            }
+        }
+        None => lines.push(Line::from(vec![
+            label("Checks:     "),
+            Span::styled("running...", styles::busy_style()),
+        ])),
+    }
+    if let Some(error) = &diagnostics.last_error {
+        let mut error_lines = error.lines();
+        lines.push(Line::from(vec![
+            label("Last error: "),
+            Span::styled(error_lines.next().unwrap_or_default().to_string(), styles::error_style()),
+        ]));
+        for line in error_lines {
+            lines.push(Line::from(Span::styled(format!("            {}", line), styles::error_style())));
+        }
+    }
+    lines.push(Line::from(""));
+    lines.push(Line::from(Span::styled(
+        format!("Stderr ({} lines)", diagnostics.stderr.len()),
+        styles::status_style().add_modifier(Modifier::BOLD),
+    )));
+
+    let width = area.width.saturating_sub(4);
+    let height = area.height.saturating_sub(2);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    // Fill the rest with the newest stderr lines that fit
+    let room = (popup.height as usize).saturating_sub(lines.len() + 3);
+    let end = diagnostics.stderr.len().saturating_sub(scroll);
+    let start = end.saturating_sub(room);
+    if diagnostics.stderr.is_empty() {
+        lines.push(Line::from(Span::styled("  (none)", styles::system_style())));
+    }
+    for line in diagnostics.stderr.range(start..end) {
+        lines.push(Line::from(Span::styled(
+            format!("  {}", line),
+            Style::default().fg(styles::TEXT),
+        )));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(styles::border_style())
+        .title(" Diagnostics ")
+        .title_bottom(" Up/Down scroll  Esc close ");
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(Text::from(lines)).block(block), popup);
+}
@@ -0,0 +1,327 @@
+//! Syntax highlighting for code blocks, files Claude reads and `cat` output
+
+use ratatui::{
+    style::{Color, Modifier, Style},
+    text::{Line, Span},
+};
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+use std::sync::OnceLock;
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{FontStyle, StyleModifier, Theme, ThemeItem, ThemeSettings};
+use syntect::parsing::{SyntaxReference, SyntaxSet};
+use syntect::util::LinesWithEndings;
+
+use super::{render_markdown, styles};
+
+/// Languages the bundled syntaxes know under another name
+const LANGUAGE_ALIASES: &[(&str, &str)] = &[
+    ("ts", "js"),
+    ("tsx", "js"),
+    ("jsx", "js"),
+    ("typescript", "js"),
+    ("shell", "sh"),
+    ("console", "sh"),
+    ("zsh", "sh"),
+    ("jsonc", "json"),
+    ("golang", "go"),
+];
+
+/// Theme colors by scope, after Catppuccin Mocha; `true` for italic
+const THEME_SCOPES: &[(&str, Color, bool)] = &[
+    ("comment, punctuation.definition.comment", styles::OVERLAY2, true),
+    ("punctuation", styles::OVERLAY2, false),
+    ("string, punctuation.definition.string", styles::GREEN, false),
+    ("constant.character.escape, constant.other.placeholder", styles::PINK, false),
+    ("constant, support.constant", styles::PEACH, false),
+    ("keyword, storage", styles::MAUVE, false),
+    ("keyword.operator, punctuation.accessor", styles::SKY, false),
+    (
+        "storage.type, entity.name.type, entity.name.class, entity.name.struct, \
+         entity.name.enum, entity.name.trait, entity.other.inherited-class, support.type, \
+         support.class",
+        styles::YELLOW,
+        false,
+    ),
+    ("entity.name.function, support.function, variable.function", styles::BLUE, false),
+    ("support.macro, entity.name.macro", styles::TEAL, false),
+    ("entity.name.namespace, entity.name.module", styles::ROSEWATER, false),
+    ("variable.parameter", styles::MAROON, false),
+    ("variable.language", styles::RED, false),
+    (
+        "variable.other.member, variable.other.property, support.type.property-name",
+        styles::LAVENDER,
+        false,
+    ),
+    ("entity.name.tag", styles::BLUE, false),
+    ("entity.other.attribute-name", styles::YELLOW, false),
+    ("markup.heading", styles::BLUE, false),
+    ("markup.inserted", styles::GREEN, false),
+    ("markup.deleted, invalid", styles::RED, false),
+    ("markup.changed, meta.diff.range", styles::PEACH, false),
+];
+
+/// Highlights code with the theme and renders finished Markdown replies,
+/// keeping the results between redraws
+#[derive(Default)]
+pub struct Highlighter {
+    /// Number the lines of code
+    pub line_numbers: bool,
+    /// Highlighted lines by language and code
+    cache: RefCell<HashMap<u64, Cached<Vec<Vec<Span<'static>>>>>>,
+    /// Rendered Markdown by text and line numbering
+    markdown: RefCell<HashMap<u64, Cached<Vec<Line<'static>>>>>,
+}
+
+struct Cached<T> {
+    value: T,
+    /// Drawn since the last sweep
+    used: bool,
+}
+
+impl Highlighter {
+    /// Lines showing `code` in `language` (a name or file extension): a
+    /// label, then each line behind a gutter, numbered from `first_line`
+    /// when line numbers are on
+    pub fn code_block(&self, code: &str, language: &str, first_line: usize) -> Vec<Line<'static>> {
+        let syntax = find_syntax(language);
+        let code_lines = match syntax {
+            Some(syntax) => self.highlight(code, syntax),
+            None => code
+                .lines()
+                .map(|line| {
+                    let line = line.replace('\t', "    ");
+                    vec![Span::styled(line, styles::code_block_style())]
+                })
+                .collect(),
+        };
+
+        let mut lines = Vec::new();
+        let label = syntax.map_or(language, |syntax| syntax.name.as_str());
+        if !label.is_empty() {
+            lines.push(Line::from(Span::styled(label.to_string(), styles::status_style())));
+        }
+        let last_line = first_line + code_lines.len().saturating_sub(1);
+        let width = last_line.to_string().len();
+        for (index, spans) in code_lines.into_iter().enumerate() {
+            let gutter = if self.line_numbers {
+                format!("{:>width$} │ ", first_line + index, width = width)
+            } else {
+                "│ ".to_string()
+            };
+            let mut line = vec![Span::styled(gutter, styles::border_style())];
+            line.extend(spans);
+            lines.push(Line::from(line));
+        }
+        lines
+    }
+
+    /// `render_markdown` for text that no longer changes, such as a
+    /// finished reply, rendered once and then reused
+    pub fn markdown(&self, text: &str) -> Vec<Line<'static>> {
+        let mut hasher = DefaultHasher::new();
+        (text, self.line_numbers).hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some(cached) = self.markdown.borrow_mut().get_mut(&key) {
+            cached.used = true;
+            return cached.value.clone();
+        }
+
+        let lines = render_markdown(text, self);
+        let cached = Cached {
+            value: lines.clone(),
+            used: true,
+        };
+        self.markdown.borrow_mut().insert(key, cached);
+        lines
+    }
+
+    /// Forget code and Markdown that wasn't drawn since the last sweep, such
+    /// as earlier versions of a block that is still streaming in
+    pub fn sweep(&self) {
+        self.cache.borrow_mut().retain(|_, cached| std::mem::take(&mut cached.used));
+        self.markdown.borrow_mut().retain(|_, cached| std::mem::take(&mut cached.used));
+    }
+
+    fn highlight(&self, code: &str, syntax: &SyntaxReference) -> Vec<Vec<Span<'static>>> {
+        let mut hasher = DefaultHasher::new();
+        (&syntax.name, code).hash(&mut hasher);
+        let key = hasher.finish();
+        if let Some(cached) = self.cache.borrow_mut().get_mut(&key) {
+            cached.used = true;
+            return cached.value.clone();
+        }
+
+        let lines = highlight_lines(code, syntax);
+        let cached = Cached {
+            value: lines.clone(),
+            used: true,
+        };
+        self.cache.borrow_mut().insert(key, cached);
+        lines
+    }
+}
+
+/// Language of a file for `code_block`, from its extension or name
+pub fn language_for_path(path: &str) -> &str {
+    let path = Path::new(path);
+    path.extension()
+        .or_else(|| path.file_name())
+        .and_then(|name| name.to_str())
+        .unwrap_or_default()
+}
+
+fn syntax_set() -> &'static SyntaxSet {
+    static SYNTAXES: OnceLock<SyntaxSet> = OnceLock::new();
+    SYNTAXES.get_or_init(SyntaxSet::load_defaults_newlines)
+}
+
+fn theme() -> &'static Theme {
+    static THEME: OnceLock<Theme> = OnceLock::new();
+    THEME.get_or_init(|| Theme {
+        name: Some("Catppuccin Mocha".to_string()),
+        settings: ThemeSettings {
+            foreground: Some(syntect_color(styles::TEXT)),
+            ..Default::default()
+        },
+        scopes: THEME_SCOPES
+            .iter()
+            .filter_map(|(selector, color, italic)| {
+                Some(ThemeItem {
+                    scope: selector.parse().ok()?,
+                    style: StyleModifier {
+                        foreground: Some(syntect_color(*color)),
+                        background: None,
+                        font_style: italic.then_some(FontStyle::ITALIC),
+                    },
+                })
+            })
+            .collect(),
+        ..Default::default()
+    })
+}
+
+/// Syntax for a language name or file extension; plain text has none
+fn find_syntax(language: &str) -> Option<&'static SyntaxReference> {
+    if language.is_empty() {
+        return None;
+    }
+    let token = LANGUAGE_ALIASES
+        .iter()
+        .find(|(alias, _)| alias.eq_ignore_ascii_case(language))
+        .map_or(language, |(_, name)| name);
+    syntax_set()
+        .find_syntax_by_token(token)
+        .filter(|syntax| syntax.name != "Plain Text")
+}
+
+fn highlight_lines(code: &str, syntax: &SyntaxReference) -> Vec<Vec<Span<'static>>> {
+    let mut highlighter = HighlightLines::new(syntax, theme());
+    LinesWithEndings::from(code)
+        .map(|line| {
+            let Ok(ranges) = highlighter.highlight_line(line, syntax_set()) else {
+                let text = line.trim_end_matches(['\n', '\r']).replace('\t', "    ");
+                return vec![Span::styled(text, styles::code_block_style())];
+            };
+            ranges
+                .into_iter()
+                .filter_map(|(style, text)| {
+                    let text = text.trim_end_matches(['\n', '\r']).replace('\t', "    ");
+                    (!text.is_empty()).then(|| Span::styled(text, ratatui_style(style)))
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn syntect_color(color: Color) -> syntect::highlighting::Color {
+    let (r, g, b) = match color {
+        Color::Rgb(r, g, b) => (r, g, b),
+        _ => (255, 255, 255),
+    };
+    syntect::highlighting::Color { r, g, b, a: 0xff }
+}
+
+fn ratatui_style(style: syntect::highlighting::Style) -> Style {
+    let fg = style.foreground;
+    let mut result = Style::default().fg(Color::Rgb(fg.r, fg.g, fg.b));
+    for (font_style, modifier) in [
+        (FontStyle::BOLD, Modifier::BOLD),
+        (FontStyle::ITALIC, Modifier::ITALIC),
+        (FontStyle::UNDERLINE, Modifier::UNDERLINED),
+    ] {
+        if style.font_style.contains(font_style) {
+            result = result.add_modifier(modifier);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(line: &Line) -> String {
+        line.spans.iter().map(|span| span.content.as_ref()).collect()
+    }
+
+    #[test]
+    fn test_code_block_highlighted_with_label_and_numbers() {
+        let mut highlighter = Highlighter::default();
+        let code = "// add\nlet s = \"hi\";\n";
+        let lines = highlighter.code_block(code, "rs", 1);
+        assert_eq!(text(&lines[0]), "Rust");
+        assert_eq!(text(&lines[1]), "│ // add");
+        let comment = &lines[1].spans[1];
+        assert_eq!(comment.style.fg, Some(styles::OVERLAY2));
+        assert!(comment.style.add_modifier.contains(Modifier::ITALIC));
+        let string = lines[2].spans.iter().find(|span| span.content.contains("hi")).unwrap();
+        assert_eq!(string.style.fg, Some(styles::GREEN));
+
+        highlighter.line_numbers = true;
+        let lines = highlighter.code_block(&"x\n".repeat(10), "", 95);
+        assert_eq!(text(&lines[0]), " 95 │ x");
+        assert_eq!(text(&lines[9]), "104 │ x");
+        assert_eq!(language_for_path("src/main.rs"), "rs");
+        assert_eq!(language_for_path("/work/Makefile"), "Makefile");
+    }
+
+    #[test]
+    fn test_highlights_cached_until_unused() {
+        let highlighter = Highlighter::default();
+        highlighter.code_block("fn main() {}", "rust", 1);
+        highlighter.code_block("fn main() {", "rust", 1);
+        assert_eq!(highlighter.cache.borrow().len(), 2);
+
+        // Only what was drawn since the last sweep is kept
+        highlighter.sweep();
+        highlighter.code_block("fn main() {}", "rust", 1);
+        highlighter.sweep();
+        assert_eq!(highlighter.cache.borrow().len(), 1);
+        highlighter.sweep();
+        assert!(highlighter.cache.borrow().is_empty());
+    }
+
+    #[test]
+    fn test_markdown_rendered_once_while_drawn() {
+        let mut highlighter = Highlighter::default();
+        let markdown = "Some **bold**\n\n```rust\nlet x = 1;\n```";
+        let lines = highlighter.markdown(markdown);
+        assert_eq!(lines, render_markdown(markdown, &highlighter));
+        highlighter.markdown(markdown);
+        assert_eq!(highlighter.markdown.borrow().len(), 1);
+
+        // Line numbers change how code blocks render
+        highlighter.line_numbers = true;
+        assert_eq!(text(&highlighter.markdown(markdown)[3]), "1 │ let x = 1;");
+        assert_eq!(highlighter.markdown.borrow().len(), 2);
+
+        highlighter.sweep();
+        highlighter.markdown(markdown);
+        highlighter.sweep();
+        assert_eq!(highlighter.markdown.borrow().len(), 1);
+    }
+}
@@ -0,0 +1,426 @@
+//! Markdown rendering for Claude's replies
+
+use pulldown_cmark::{Alignment, CodeBlockKind, Event, Options, Parser, Tag, TagEnd};
+use ratatui::{
+    style::{Modifier, Style},
+    text::{Line, Span},
+};
+
+use super::{styles, Highlighter};
+
+/// Bullets for each level of nested lists
+const BULLETS: [&str; 3] = ["•", "◦", "▪"];
+
+/// Width of a horizontal rule
+const RULE_WIDTH: usize = 40;
+
+/// Render Markdown `text` as styled lines.
+///
+/// Text that is still streaming in renders as far as it goes: an open code
+/// fence runs to the end of the text and a table shows the rows so far.
+pub fn render_markdown(text: &str, highlighter: &Highlighter) -> Vec<Line<'static>> {
+    let options =
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TASKLISTS;
+    let mut renderer = Renderer::new(highlighter);
+    for event in Parser::new_ext(text, options) {
+        renderer.event(event);
+    }
+    renderer.finish()
+}
+
+/// Block whose lines are prefixed
+enum Container {
+    Quote,
+    /// List item; the marker goes on its first line, indentation on the rest
+    Item { marker: Option<String>, width: usize },
+}
+
+#[derive(Default)]
+struct Table {
+    alignments: Vec<Alignment>,
+    /// Rows of cells, the header first
+    rows: Vec<Vec<Vec<Span<'static>>>>,
+    row: Vec<Vec<Span<'static>>>,
+    cell: Vec<Span<'static>>,
+}
+
+struct Renderer<'a> {
+    highlighter: &'a Highlighter,
+    lines: Vec<Line<'static>>,
+    /// Spans of the line being built
+    spans: Vec<Span<'static>>,
+    /// Inline styles in effect, innermost last
+    styles: Vec<Style>,
+    containers: Vec<Container>,
+    /// Next number of each open list, `None` for bullet lists
+    lists: Vec<Option<u64>>,
+    /// Language and text of the open code block
+    code: Option<(String, String)>,
+    table: Option<Table>,
+    /// Target and text of the open link
+    link: Option<(String, String)>,
+    /// A blank line goes before the next block
+    needs_blank: bool,
+}
+
+impl<'a> Renderer<'a> {
+    fn new(highlighter: &'a Highlighter) -> Self {
+        Self {
+            highlighter,
+            lines: Vec::new(),
+            spans: Vec::new(),
+            styles: Vec::new(),
+            containers: Vec::new(),
+            lists: Vec::new(),
+            code: None,
+            table: None,
+            link: None,
+            needs_blank: false,
+        }
+    }
+
+    fn event(&mut self, event: Event) {
+        match event {
+            Event::Start(tag) => self.start(tag),
+            Event::End(tag) => self.end(tag),
+            Event::Text(text) => match &mut self.code {
+                Some((_, code)) => code.push_str(&text),
+                None => self.push_text(&text, self.style()),
+            },
+            Event::Code(code) => {
+                self.push_text(&code, self.style().patch(styles::inline_code_style()))
+            }
+            Event::Html(html) | Event::InlineHtml(html) => self.push_text(&html, self.style()),
+            Event::FootnoteReference(label) => {
+                self.push_text(&format!("[^{}]", label), self.style())
+            }
+            // Keep Claude's line breaks rather than reflowing paragraphs
+            Event::SoftBreak | Event::HardBreak => self.flush_line(),
+            Event::Rule => {
+                self.start_block();
+                self.spans.push(Span::styled("─".repeat(RULE_WIDTH), styles::border_style()));
+                self.flush_line();
+                self.needs_blank = true;
+            }
+            Event::TaskListMarker(done) => {
+                let marker = if done { "[x] " } else { "[ ] " };
+                self.spans.push(Span::styled(marker, styles::list_marker_style()));
+            }
+            _ => {}
+        }
+    }
+
+    fn start(&mut self, tag: Tag) {
+        match tag {
+            Tag::Paragraph | Tag::HtmlBlock => self.start_block(),
+            Tag::Heading { level, .. } => {
+                self.start_block();
+                self.styles.push(styles::heading_style(level as u8));
+            }
+            Tag::BlockQuote(_) => {
+                self.start_block();
+                self.containers.push(Container::Quote);
+                self.styles.push(styles::quote_style());
+            }
+            Tag::CodeBlock(kind) => {
+                self.start_block();
+                let language = match kind {
+                    CodeBlockKind::Fenced(info) => {
+                        info.split_whitespace().next().unwrap_or_default().to_string()
+                    }
+                    CodeBlockKind::Indented => String::new(),
+                };
+                self.code = Some((language, String::new()));
+            }
+            Tag::List(start) => {
+                self.start_block();
+                self.lists.push(start);
+            }
+            Tag::Item => {
+                self.flush_line();
+                // Items are kept together, even in loose lists
+                self.needs_blank = false;
+                let depth = self.lists.len().saturating_sub(1);
+                let marker = match self.lists.last_mut() {
+                    Some(Some(number)) => {
+                        *number += 1;
+                        format!("{}. ", *number - 1)
+                    }
+                    _ => format!("{} ", BULLETS[depth % BULLETS.len()]),
+                };
+                let width = marker.chars().count();
+                self.containers.push(Container::Item {
+                    marker: Some(marker),
+                    width,
+                });
+            }
+            Tag::Table(alignments) => {
+                self.start_block();
+                self.table = Some(Table {
+                    alignments,
+                    ..Default::default()
+                });
+            }
+            Tag::TableHead => self.styles.push(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Emphasis => self.styles.push(Style::default().add_modifier(Modifier::ITALIC)),
+            Tag::Strong => self.styles.push(Style::default().add_modifier(Modifier::BOLD)),
+            Tag::Strikethrough => {
+                self.styles.push(Style::default().add_modifier(Modifier::CROSSED_OUT))
+            }
+            Tag::Link { dest_url, .. } | Tag::Image { dest_url, .. } => {
+                self.styles.push(styles::link_style());
+                self.link = Some((dest_url.to_string(), String::new()));
+            }
+            _ => {}
+        }
+    }
+
+    fn end(&mut self, tag: TagEnd) {
+        match tag {
+            TagEnd::Paragraph | TagEnd::HtmlBlock => {
+                self.flush_line();
+                self.needs_blank = true;
+            }
+            TagEnd::Heading(_) => {
+                self.flush_line();
+                self.styles.pop();
+                self.needs_blank = true;
+            }
+            TagEnd::BlockQuote(_) => {
+                self.flush_line();
+                self.containers.pop();
+                self.styles.pop();
+                self.needs_blank = true;
+            }
+            TagEnd::CodeBlock => {
+                self.push_code_block();
+                self.needs_blank = true;
+            }
+            TagEnd::List(_) => {
+                self.flush_line();
+                self.lists.pop();
+                self.needs_blank = true;
+            }
+            TagEnd::Item => {
+                self.flush_line();
+                self.containers.pop();
+            }
+            TagEnd::Table => {
+                self.push_table();
+                self.needs_blank = true;
+            }
+            TagEnd::TableHead | TagEnd::TableRow => {
+                if tag == TagEnd::TableHead {
+                    self.styles.pop();
+                }
+                if let Some(table) = &mut self.table {
+                    let row = std::mem::take(&mut table.row);
+                    table.rows.push(row);
+                }
+            }
+            TagEnd::TableCell => {
+                if let Some(table) = &mut self.table {
+                    let cell = std::mem::take(&mut table.cell);
+                    table.row.push(cell);
+                }
+            }
+            TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough => {
+                self.styles.pop();
+            }
+            TagEnd::Link | TagEnd::Image => {
+                self.styles.pop();
+                // Show where a link goes unless its text already says
+                if let Some((url, text)) = self.link.take() {
+                    if !url.is_empty() && url != text {
+                        self.push_span(Span::styled(format!(" ({})", url), styles::status_style()));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn finish(mut self) -> Vec<Line<'static>> {
+        self.flush_line();
+        self.lines
+    }
+
+    /// Style for text at the current position
+    fn style(&self) -> Style {
+        self.styles
+            .iter()
+            .fold(Style::default().fg(styles::TEXT), |style, inner| style.patch(*inner))
+    }
+
+    fn push_text(&mut self, text: &str, style: Style) {
+        if let Some((_, link_text)) = &mut self.link {
+            link_text.push_str(text);
+        }
+        for (index, piece) in text.split('\n').enumerate() {
+            if index > 0 {
+                self.flush_line();
+            }
+            if !piece.is_empty() {
+                self.push_span(Span::styled(piece.to_string(), style));
+            }
+        }
+    }
+
+    /// Add to the open table cell, or else the current line
+    fn push_span(&mut self, span: Span<'static>) {
+        match &mut self.table {
+            Some(table) => table.cell.push(span),
+            None => self.spans.push(span),
+        }
+    }
+
+    /// Finish the current line and separate the block about to start
+    fn start_block(&mut self) {
+        self.flush_line();
+        if self.needs_blank {
+            let quotes = self
+                .containers
+                .iter()
+                .filter(|container| matches!(container, Container::Quote))
+                .count();
+            self.lines.push(Line::from(Span::styled("▎ ".repeat(quotes), styles::border_style())));
+            self.needs_blank = false;
+        }
+    }
+
+    /// Move the current spans into a line, after the containers' prefixes
+    fn flush_line(&mut self) {
+        if self.spans.is_empty() {
+            return;
+        }
+        let mut line = Vec::new();
+        for container in &mut self.containers {
+            match container {
+                Container::Quote => line.push(Span::styled("▎ ", styles::border_style())),
+                Container::Item { marker, width } => match marker.take() {
+                    Some(marker) => line.push(Span::styled(marker, styles::list_marker_style())),
+                    None => line.push(Span::raw(" ".repeat(*width))),
+                },
+            }
+        }
+        line.append(&mut self.spans);
+        self.lines.push(Line::from(line));
+    }
+
+    fn push_code_block(&mut self) {
+        let Some((language, code)) = self.code.take() else {
+            return;
+        };
+        for line in self.highlighter.code_block(&code, &language, 1) {
+            self.spans = line.spans;
+            self.flush_line();
+        }
+    }
+
+    /// Table rows with aligned columns and a rule under the header
+    fn push_table(&mut self) {
+        let Some(Table {
+            alignments, rows, ..
+        }) = self.table.take()
+        else {
+            return;
+        };
+        let cell_width = |cell: &[Span]| cell.iter().map(Span::width).sum::<usize>();
+        let columns = rows.iter().map(Vec::len).max().unwrap_or(0);
+        let mut widths = vec![0; columns];
+        for row in &rows {
+            for (column, cell) in row.iter().enumerate() {
+                widths[column] = widths[column].max(cell_width(cell));
+            }
+        }
+
+        for (index, row) in rows.into_iter().enumerate() {
+            // Rows still streaming in may be short of cells
+            let mut cells = row.into_iter();
+            for (column, width) in widths.iter().enumerate() {
+                if column > 0 {
+                    self.spans.push(Span::styled(" │ ", styles::border_style()));
+                }
+                let cell = cells.next().unwrap_or_default();
+                let padding = width - cell_width(&cell);
+                let (left, right) = match alignments.get(column) {
+                    Some(Alignment::Right) => (padding, 0),
+                    Some(Alignment::Center) => (padding / 2, padding - padding / 2),
+                    _ => (0, padding),
+                };
+                if left > 0 {
+                    self.spans.push(Span::raw(" ".repeat(left)));
+                }
+                self.spans.extend(cell);
+                if right > 0 {
+                    self.spans.push(Span::raw(" ".repeat(right)));
+                }
+            }
+            self.flush_line();
+            if index == 0 {
+                let rule: Vec<String> = widths.iter().map(|width| "─".repeat(*width)).collect();
+                self.spans.push(Span::styled(rule.join("─┼─"), styles::border_style()));
+                self.flush_line();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn text(lines: &[Line]) -> Vec<String> {
+        lines
+            .iter()
+            .map(|line| line.spans.iter().map(|span| span.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_render_markdown() {
+        let lines = render_markdown(
+            "# Title\n\nSome **bold** and `code`.\n\n- one\n- two\n  1. first\n\n\
+             > quoted\n\n[docs](https://example.com)",
+            &Highlighter::default(),
+        );
+        assert_eq!(
+            text(&lines),
+            [
+                "Title",
+                "",
+                "Some bold and code.",
+                "",
+                "• one",
+                "• two",
+                "  1. first",
+                "",
+                "▎ quoted",
+                "",
+                "docs (https://example.com)",
+            ]
+        );
+        let heading = Style::default().fg(styles::TEXT).patch(styles::heading_style(1));
+        assert_eq!(lines[0].spans[0].style, heading);
+        let bold = &lines[2].spans[1];
+        assert_eq!(bold.content, "bold");
+        assert!(bold.style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(lines[2].spans[3].style.bg, styles::inline_code_style().bg);
+    }
+
+    #[test]
+    fn test_render_incomplete_markdown() {
+        // A fence that isn't closed yet
+        let highlighter = Highlighter::default();
+        let lines = render_markdown("Like this:\n\n```rust\nfn main() {\n\tlet x", &highlighter);
+        assert_eq!(text(&lines), ["Like this:", "", "Rust", "│ fn main() {", "│     let x"]);
+
+        // A table with its last row half written
+        let table = "| Name | Size |\n|---|--:|\n| a.rs | 10 |\n| main.rs";
+        let lines = render_markdown(table, &highlighter);
+        assert_eq!(
+            text(&lines),
+            ["Name    │ Size", "────────┼─────", "a.rs    │   10", "main.rs │     "]
+        );
+    }
+}
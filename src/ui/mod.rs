@@ -1,20 +1,42 @@
 //! UI components using ratatui
 
+mod compact;
+mod completion;
 mod conversation;
+mod diagnostics;
+mod highlight;
 mod input;
 mod layout;
+mod markdown;
+mod model_picker;
+mod permission;
+mod restore;
+mod resume_picker;
 mod status;
 mod styles;
 
+pub use compact::*;
+pub use completion::*;
 pub use conversation::*;
+pub use diagnostics::*;
+pub use highlight::*;
 pub use input::*;
 pub use layout::*;
+pub use markdown::*;
+pub use model_picker::*;
+pub use permission::*;
+pub use restore::*;
+pub use resume_picker::*;
 pub use status::*;
 pub use styles::*;
 
 use ratatui::Frame;
+use std::path::Path;
 
-use crate::app::{ConversationEntry, TokenUsage};
+use crate::app::{ConversationEntry, Diagnostics, SessionCost, TokenUsage};
+use crate::claude::{PermissionMode, TranscriptSummary};
+use crate::permissions::PermissionRequest;
+use crate::store::SavedState;
 
 /// Input mode for the application
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -34,10 +56,46 @@ pub struct RenderState<'a> {
     pub claude_busy: bool,
     pub streaming_buffer: &'a str,
     pub model: &'a str,
+    pub working_dir: &'a Path,
+    /// A budget limit was hit and messages are held
+    pub budget_exceeded: bool,
+    pub permission_mode: PermissionMode,
     pub scroll_offset: usize,
+    pub expand_subagents: bool,
     pub status_message: Option<&'a str>,
     pub token_usage: &'a TokenUsage,
+    pub session_cost: &'a SessionCost,
     pub message_queue_len: usize,
+    pub permission_request: Option<&'a PermissionRequest>,
+    pub pending_permissions: usize,
+    /// "Always allow" was chosen and awaits confirmation
+    pub confirm_always_allow: bool,
+    /// Set while the diagnostics panel is open
+    pub diagnostics: Option<&'a Diagnostics>,
+    pub diagnostics_scroll: usize,
+    /// Highlighted row while the model picker is open
+    pub model_picker: Option<usize>,
+    /// Past sessions and the highlighted row while the resume picker is open
+    pub resume_picker: Option<(&'a [TranscriptSummary], usize)>,
+    /// Context window of the model in use, in tokens
+    pub context_window: u64,
+    /// Fraction of the context window at which /compact is offered
+    pub compact_at: f64,
+    /// The /compact offer is open
+    pub compact_offer: bool,
+    /// State left by a crashed session, while offered for restoring
+    pub restore_offer: Option<&'a SavedState>,
+    /// Commands matching a partly typed slash command
+    pub completions: Vec<Completion<'a>>,
+    /// Highlights code in the conversation
+    pub highlighter: &'a Highlighter,
+}
+
+impl RenderState<'_> {
+    /// Fraction of the context window the latest request used
+    pub fn context_used(&self) -> f64 {
+        self.token_usage.context_tokens as f64 / self.context_window.max(1) as f64
+    }
 }
 
 /// Main draw function
@@ -52,4 +110,35 @@ pub fn draw(frame: &mut Frame, state: &RenderState) {
 
     // Draw status bar
     draw_status(frame, chunks[2], state);
+
+    if !state.completions.is_empty() {
+        draw_completions(frame, chunks[1], &state.completions);
+    }
+
+    if let Some(diagnostics) = state.diagnostics {
+        draw_diagnostics(frame, frame.area(), diagnostics, state.diagnostics_scroll);
+    }
+    if let Some(selected) = state.model_picker {
+        draw_model_picker(frame, frame.area(), selected, state.model);
+    }
+    if let Some((sessions, selected)) = state.resume_picker {
+        draw_resume_picker(frame, frame.area(), sessions, selected);
+    }
+    if state.compact_offer {
+        draw_compact_offer(frame, frame.area(), state.context_used());
+    }
+    if let Some(saved) = state.restore_offer {
+        draw_restore_offer(frame, frame.area(), saved);
+    }
+
+    // Permission prompt over everything else
+    if let Some(request) = state.permission_request {
+        draw_permission_prompt(
+            frame,
+            frame.area(),
+            request,
+            state.pending_permissions,
+            state.confirm_always_allow,
+        );
+    }
 }
@@ -0,0 +1,53 @@
+//! Model picker overlay
+
+use ratatui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::claude::MODELS;
+
+use super::{format_tokens, styles};
+
+/// Draw the model list centered over `area`, highlighting `selected` and
+/// marking the model currently in use
+pub fn draw_model_picker(frame: &mut Frame, area: Rect, selected: usize, current: &str) {
+    let mut lines: Vec<Line> = Vec::new();
+    for (index, model) in MODELS.iter().enumerate() {
+        let marker = if model.matches(current) { "* " } else { "  " };
+        let mut style = styles::input_style();
+        if index == selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        lines.push(Line::from(vec![
+            Span::styled(marker, styles::token_style()),
+            Span::styled(format!("{:<28}", model.cli_name()), style),
+            Span::styled(format!("{:<20}", model.name), styles::status_style()),
+            Span::styled(
+                format!("{} context", format_tokens(model.context_window)),
+                styles::system_style(),
+            ),
+        ]));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(styles::border_style())
+        .title(" Model ")
+        .title_bottom(" Up/Down select  Enter use  Esc cancel ");
+
+    let width = area.width.saturating_sub(4).min(76);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(Text::from(lines)).block(block), popup);
+}
@@ -0,0 +1,97 @@
+//! Permission prompt modal
+
+use ratatui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::permissions::{rule_for, PermissionRequest};
+
+use super::styles;
+
+/// Maximum lines of tool input shown in the prompt
+const MAX_INPUT_LINES: usize = 12;
+
+/// Draw the permission prompt centered over `area`; `confirm_always` asks
+/// to confirm saving an "always allow" rule
+pub fn draw_permission_prompt(
+    frame: &mut Frame,
+    area: Rect,
+    request: &PermissionRequest,
+    pending: usize,
+    confirm_always: bool,
+) {
+    let input = serde_json::to_string_pretty(&request.input).unwrap_or_default();
+    let input_lines: Vec<&str> = input.lines().collect();
+
+    let mut lines: Vec<Line> = Vec::new();
+    lines.push(Line::from(vec![
+        Span::styled("Claude wants to use ", styles::input_style()),
+        Span::styled(
+            request.tool_name.clone(),
+            styles::tool_style().add_modifier(Modifier::BOLD),
+        ),
+    ]));
+    lines.push(Line::from(""));
+    for line in input_lines.iter().take(MAX_INPUT_LINES) {
+        lines.push(Line::from(Span::styled(format!("  {}", line), styles::tool_style())));
+    }
+    if input_lines.len() > MAX_INPUT_LINES {
+        lines.push(Line::from(Span::styled("  ...", styles::system_style())));
+    }
+    lines.push(Line::from(""));
+    if confirm_always {
+        lines.push(Line::from(vec![
+            Span::styled("Always allow ", styles::input_style()),
+            Span::styled(rule_for(&request.tool_name, &request.input), styles::tool_style()),
+            Span::styled(" in this project?", styles::input_style()),
+        ]));
+        lines.push(Line::from(vec![
+            Span::styled("[a]", styles::token_style()),
+            Span::styled(" Save rule  ", styles::status_style()),
+            Span::styled("[y]", styles::token_style()),
+            Span::styled(" Allow once  ", styles::status_style()),
+            Span::styled("[n]", styles::error_style()),
+            Span::styled(" Deny  ", styles::status_style()),
+            Span::styled("[Esc]", styles::token_style()),
+            Span::styled(" Back", styles::status_style()),
+        ]));
+    } else {
+        lines.push(Line::from(vec![
+            Span::styled("[y]", styles::token_style()),
+            Span::styled(" Allow once  ", styles::status_style()),
+            Span::styled("[a]", styles::token_style()),
+            Span::styled(" Always allow in this project  ", styles::status_style()),
+            Span::styled("[n]", styles::error_style()),
+            Span::styled(" Deny", styles::status_style()),
+        ]));
+    }
+
+    let title = if pending > 1 {
+        format!(" Permission required (1 of {}) ", pending)
+    } else {
+        " Permission required ".to_string()
+    };
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(styles::busy_style())
+        .title(title);
+
+    let width = area.width.saturating_sub(4).min(90);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(Text::from(lines)).block(block).wrap(Wrap { trim: false }),
+        popup,
+    );
+}
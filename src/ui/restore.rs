@@ -0,0 +1,61 @@
+//! Offer to restore the view a crashed session left behind
+
+use ratatui::{
+    layout::Rect,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph, Wrap},
+    Frame,
+};
+
+use crate::store::SavedState;
+
+use super::styles;
+
+/// Draw the restore offer centered over `area`
+pub fn draw_restore_offer(frame: &mut Frame, area: Rect, saved: &SavedState) {
+    let mut saved_parts = vec![format!("{} conversation entries", saved.entries.len())];
+    if !saved.snapshot.message_queue.is_empty() {
+        saved_parts.push(format!("{} queued messages", saved.snapshot.message_queue.len()));
+    }
+    if !saved.snapshot.input.is_empty() {
+        saved_parts.push("an unsent draft".to_string());
+    }
+
+    let lines = vec![
+        Line::from(Span::styled(
+            "The last session in this directory didn't exit cleanly.",
+            styles::input_style(),
+        )),
+        Line::from(Span::styled(
+            format!("Saved: {}.", saved_parts.join(", ")),
+            styles::status_style(),
+        )),
+        Line::from(""),
+        Line::from(vec![
+            Span::styled("[y]", styles::token_style()),
+            Span::styled(" Restore  ", styles::status_style()),
+            Span::styled("[n]", styles::error_style()),
+            Span::styled(" Start fresh", styles::status_style()),
+        ]),
+    ];
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(styles::busy_style())
+        .title(" Restore session ");
+
+    let width = area.width.saturating_sub(4).min(84);
+    let height = (lines.len() as u16 + 2).min(area.height);
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(
+        Paragraph::new(Text::from(lines)).block(block).wrap(Wrap { trim: false }),
+        popup,
+    );
+}
@@ -0,0 +1,62 @@
+//! Picker for resuming a past Claude session
+
+use ratatui::{
+    layout::Rect,
+    style::Modifier,
+    text::{Line, Span, Text},
+    widgets::{Block, Borders, Clear, Paragraph},
+    Frame,
+};
+
+use crate::claude::TranscriptSummary;
+
+use super::styles;
+
+/// Draw the past sessions centered over `area`, highlighting `selected`.
+/// The list scrolls to keep the selection in view.
+pub fn draw_resume_picker(
+    frame: &mut Frame,
+    area: Rect,
+    sessions: &[TranscriptSummary],
+    selected: usize,
+) {
+    let width = area.width.saturating_sub(4).min(100);
+    let height = (sessions.len().max(1) as u16 + 2).min(area.height.saturating_sub(2));
+    let rows = height.saturating_sub(2).max(1) as usize;
+    let first = selected.saturating_sub(rows - 1);
+    // Borders, date and message count take 32 columns
+    let prompt_width = (width as usize).saturating_sub(32);
+
+    let mut lines: Vec<Line> = Vec::new();
+    for (index, session) in sessions.iter().enumerate().skip(first).take(rows) {
+        let mut style = styles::input_style();
+        if index == selected {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        let prompt: String = session.first_prompt.chars().take(prompt_width).collect();
+        lines.push(Line::from(vec![
+            Span::styled(
+                format!(" {}  ", session.modified.format("%Y-%m-%d %H:%M")),
+                styles::status_style(),
+            ),
+            Span::styled(format!("{:>4} msgs  ", session.message_count), styles::token_style()),
+            Span::styled(prompt, style),
+        ]));
+    }
+
+    let block = Block::default()
+        .borders(Borders::ALL)
+        .border_style(styles::border_style())
+        .title(" Resume session ")
+        .title_bottom(" Up/Down select  Enter resume  Esc cancel ");
+
+    let popup = Rect {
+        x: area.x + (area.width.saturating_sub(width)) / 2,
+        y: area.y + (area.height.saturating_sub(height)) / 2,
+        width,
+        height,
+    };
+
+    frame.render_widget(Clear, popup);
+    frame.render_widget(Paragraph::new(Text::from(lines)).block(block), popup);
+}
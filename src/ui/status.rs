@@ -1,5 +1,7 @@
 //! Status bar widget
 
+use std::path::Path;
+
 use ratatui::{
     layout::Rect,
     text::{Line, Span},
@@ -7,8 +9,13 @@ use ratatui::{
     Frame,
 };
 
+use crate::claude::PermissionMode;
+
 use super::{styles, RenderState};
 
+/// The context gauge turns yellow at this share of the compact threshold
+const CONTEXT_WARN_RATIO: f64 = 0.75;
+
 /// Draw the status bar
 pub fn draw_status(frame: &mut Frame, area: Rect, state: &RenderState) {
     let mut spans = vec![];
@@ -20,12 +27,30 @@ pub fn draw_status(frame: &mut Frame, area: Rect, state: &RenderState) {
     ));
     spans.push(Span::styled(" | ", styles::status_style()));
 
+    // Working directory
+    spans.push(Span::styled(display_dir(state.working_dir), styles::status_style()));
+    spans.push(Span::styled(" | ", styles::status_style()));
+
+    // Permission mode
+    let mode_style = match state.permission_mode {
+        PermissionMode::Default => styles::status_style(),
+        PermissionMode::Plan => styles::model_style(),
+        PermissionMode::AcceptEdits => styles::busy_style(),
+        PermissionMode::BypassPermissions => styles::error_style(),
+    };
+    spans.push(Span::styled(state.permission_mode.as_str(), mode_style));
+    spans.push(Span::styled(" | ", styles::status_style()));
+
     // Status indicator
     if state.claude_busy {
         spans.push(Span::styled("Processing...", styles::busy_style()));
     } else {
         spans.push(Span::styled("Ready", styles::token_style()));
     }
+    if state.budget_exceeded {
+        spans.push(Span::styled(" | ", styles::status_style()));
+        spans.push(Span::styled("Budget reached", styles::error_style()));
+    }
 
     // Queue
     if state.message_queue_len > 0 {
@@ -42,21 +67,50 @@ pub fn draw_status(frame: &mut Frame, area: Rect, state: &RenderState) {
         spans.push(Span::styled(msg, styles::status_style()));
     }
 
-    // Token usage (right aligned)
+    // Cost and token usage (right aligned)
     let usage = state.token_usage;
-    let token_info = format!(
+    let cost = state.session_cost;
+    let mut right = Vec::new();
+
+    // Context gauge, once a request has reported its size
+    if usage.context_tokens > 0 {
+        let used = state.context_used();
+        let style = if used >= state.compact_at {
+            styles::error_style()
+        } else if used >= state.compact_at * CONTEXT_WARN_RATIO {
+            styles::busy_style()
+        } else {
+            styles::token_style()
+        };
+        right.push(Span::styled(format!("Ctx: {:.0}%  ", used * 100.0), style));
+    }
+
+    let mut token_info = String::new();
+    if let Some(last) = &cost.last {
+        token_info.push_str(&format!(
+            "Last: {} {} {} turns  Total: {}  ",
+            format_cost(last.cost_usd),
+            format_duration(last.duration_ms),
+            last.num_turns,
+            format_cost(cost.cost_usd)
+        ));
+    }
+    token_info.push_str(&format!(
         "In: {} Out: {} ",
         format_tokens(usage.input_tokens),
         format_tokens(usage.output_tokens)
-    );
+    ));
+
+    right.push(Span::styled(token_info, styles::token_style()));
 
     // Calculate padding to right-align
     let left_len: usize = spans.iter().map(|s| s.content.len()).sum();
-    let padding = (area.width as usize).saturating_sub(left_len + token_info.len());
+    let right_len: usize = right.iter().map(|s| s.content.len()).sum();
+    let padding = (area.width as usize).saturating_sub(left_len + right_len);
     if padding > 0 {
         spans.push(Span::raw(" ".repeat(padding)));
     }
-    spans.push(Span::styled(token_info, styles::token_style()));
+    spans.extend(right);
 
     let line = Line::from(spans);
     let paragraph = Paragraph::new(line);
@@ -64,7 +118,16 @@ pub fn draw_status(frame: &mut Frame, area: Rect, state: &RenderState) {
     frame.render_widget(paragraph, area);
 }
 
-fn format_tokens(tokens: u64) -> String {
+/// A directory with the home prefix shortened to `~`
+fn display_dir(dir: &Path) -> String {
+    match dirs::home_dir().and_then(|home| dir.strip_prefix(home).ok()) {
+        Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+        Some(rest) => format!("~/{}", rest.display()),
+        None => dir.display().to_string(),
+    }
+}
+
+pub fn format_tokens(tokens: u64) -> String {
     if tokens >= 1_000_000 {
         format!("{:.1}M", tokens as f64 / 1_000_000.0)
     } else if tokens >= 1_000 {
@@ -73,3 +136,19 @@ fn format_tokens(tokens: u64) -> String {
         tokens.to_string()
     }
 }
+
+pub fn format_cost(usd: f64) -> String {
+    if usd < 0.01 {
+        format!("${:.4}", usd)
+    } else {
+        format!("${:.2}", usd)
+    }
+}
+
+pub fn format_duration(ms: u64) -> String {
+    if ms >= 60_000 {
+        format!("{}m{:02}s", ms / 60_000, (ms % 60_000) / 1_000)
+    } else {
+        format!("{:.1}s", ms as f64 / 1_000.0)
+    }
+}
@@ -94,3 +94,32 @@ pub fn model_style() -> Style {
 pub fn token_style() -> Style {
     Style::default().fg(GREEN)
 }
+
+// Markdown styles
+pub fn heading_style(level: u8) -> Style {
+    match level {
+        1 => Style::default().fg(MAUVE).add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+        2 => Style::default().fg(MAUVE).add_modifier(Modifier::BOLD),
+        _ => Style::default().fg(LAVENDER).add_modifier(Modifier::BOLD),
+    }
+}
+
+pub fn inline_code_style() -> Style {
+    Style::default().fg(PEACH).bg(SURFACE0)
+}
+
+pub fn code_block_style() -> Style {
+    Style::default().fg(TEXT)
+}
+
+pub fn quote_style() -> Style {
+    Style::default().fg(SUBTEXT1).add_modifier(Modifier::ITALIC)
+}
+
+pub fn link_style() -> Style {
+    Style::default().fg(BLUE).add_modifier(Modifier::UNDERLINED)
+}
+
+pub fn list_marker_style() -> Style {
+    Style::default().fg(TEAL)
+}
@@ -7,7 +7,7 @@ use std::sync::{
 };
 use tokio::sync::mpsc;
 
-use crate::app::AppMessage;
+use crate::app::{AppMessage, RequestId};
 
 use super::whisper::transcribe;
 
@@ -29,8 +29,8 @@ impl VoiceRecorder {
         }
     }
 
-    /// Start recording audio
-    pub async fn start(&self) -> Result<()> {
+    /// Start recording audio for voice job `id`
+    pub async fn start(&self, id: RequestId) -> Result<()> {
         // Clear previous samples
         {
             let mut samples = self.samples.lock().unwrap();
@@ -48,15 +48,15 @@ impl VoiceRecorder {
         std::thread::spawn(move || {
             if let Err(e) = run_recording(samples, sample_rate_store, recording) {
                 tracing::error!("Recording error: {}", e);
-                let _ = tx.blocking_send(AppMessage::VoiceError(e.to_string()));
+                let _ = tx.blocking_send(AppMessage::VoiceError(id, e.to_string()));
             }
         });
 
         Ok(())
     }
 
-    /// Stop recording and transcribe
-    pub async fn stop(&self) -> Result<()> {
+    /// Stop recording and transcribe for voice job `id`
+    pub async fn stop(&self, id: RequestId) -> Result<()> {
         self.recording.store(false, Ordering::SeqCst);
 
         // Give time for the stream to finish
@@ -74,7 +74,7 @@ impl VoiceRecorder {
 
         if samples.is_empty() {
             self.message_tx
-                .send(AppMessage::VoiceError("No audio recorded".to_string()))
+                .send(AppMessage::VoiceError(id, "No audio recorded".to_string()))
                 .await?;
             return Ok(());
         }
@@ -85,10 +85,10 @@ impl VoiceRecorder {
         tokio::spawn(async move {
             match transcribe(&samples, sample_rate).await {
                 Ok(text) => {
-                    let _ = tx.send(AppMessage::VoiceTranscription(text)).await;
+                    let _ = tx.send(AppMessage::VoiceTranscription(id, text)).await;
                 }
                 Err(e) => {
-                    let _ = tx.send(AppMessage::VoiceError(e.to_string())).await;
+                    let _ = tx.send(AppMessage::VoiceError(id, e.to_string())).await;
                 }
             }
         });
@@ -0,0 +1,39 @@
+#!/bin/sh
+# Mock Claude CLI for end-to-end tests.
+#
+# MOCK_CLAUDE_SCENARIO names a file of canned stream-json lines. A line
+# containing only "---" separates turns, and "sleep <secs>" pauses. One turn
+# is played for each message read from stdin. "stderr <text>" writes to
+# stderr and "exit <code>" ends the process.
+#
+# MOCK_CLAUDE_LOG, if set, receives the arguments, working directory and
+# every stdin line.
+
+log() {
+    if [ -n "$MOCK_CLAUDE_LOG" ]; then
+        printf '%s\n' "$1" >> "$MOCK_CLAUDE_LOG"
+    fi
+}
+
+case "$1" in
+    --version) echo "0.0.0-mock (Claude Code)"; exit 0 ;;
+    --help) echo "--input-format --include-partial-messages --permission-prompt-tool"; exit 0 ;;
+esac
+
+log "args: $*"
+log "cwd: $(pwd)"
+
+turn=0
+while IFS= read -r input; do
+    log "$input"
+    awk -v turn="$turn" '$0 == "---" { n++; next } n == turn' "$MOCK_CLAUDE_SCENARIO" |
+        while IFS= read -r line; do
+            case "$line" in
+                "sleep "*) sleep "${line#sleep }" ;;
+                "stderr "*) printf '%s\n' "${line#stderr }" >&2 ;;
+                "exit "*) exit "${line#exit }" ;;
+                *) printf '%s\n' "$line" ;;
+            esac
+        done || exit $?
+    turn=$((turn + 1))
+done